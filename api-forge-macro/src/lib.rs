@@ -1,4 +1,5 @@
-use darling::{FromDeriveInput, FromField};
+use darling::ast::NestedMeta;
+use darling::{FromDeriveInput, FromField, FromMeta};
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
 use quote::quote;
@@ -7,30 +8,535 @@ use syn::{parse_macro_input, DeriveInput, LitStr, Error as SynError};
 #[derive(Debug, FromDeriveInput, Clone)]
 #[darling(attributes(request))]
 struct RequestArgs {
-    data: darling::ast::Data<(), HeaderField>,
+    data: darling::ast::Data<(), RequestField>,
     endpoint: String,
     #[darling(default, rename = "response_type")]
     response_type: Option<String>,
+    #[darling(default)]
+    error_type: Option<String>,
     #[darling(default, rename = "method")]
     method: Option<syn::Path>,
     #[darling(default, rename = "transmission")]
     transmission: Option<syn::Path>,
     #[darling(default, rename = "authentication")]
-    authentication: Option<syn::Path>,
+    authentication: Option<AuthenticationArg>,
     #[darling(default, rename = "path_parameters")]
     path_parameters: Option<Vec<LitStr>>,
     #[darling(default)]
     accept: Option<LitStr>,
     #[darling(default)]
     content_type: Option<LitStr>,
+    #[darling(default)]
+    retry: Option<RetryArg>,
+    #[darling(default)]
+    multipart_form: Option<syn::Path>,
+    #[darling(default)]
+    xml: Option<XmlArgs>,
+}
+
+/// The `xml` attribute, configuring the body of `Xml` requests.
+#[derive(Debug, FromMeta, Clone, Default)]
+struct XmlArgs {
+    #[darling(default)]
+    root: Option<LitStr>,
+    #[darling(default)]
+    namespace: Option<LitStr>,
+    #[darling(default)]
+    namespaces: Option<std::collections::HashMap<Ident, LitStr>>,
+}
+
+impl XmlArgs {
+    /// Generates the `api_forge::XmlOptions` for the request, defaulting the root element to the
+    /// serialized name of the struct.
+    fn to_options(&self, input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+        let root = match &self.root {
+            Some(root) => root.value(),
+            None => serde_rename(&input.attrs)?.unwrap_or_else(|| input.ident.to_string()),
+        };
+
+        let mut namespaces = self
+            .namespace
+            .iter()
+            .map(|uri| (String::new(), uri.value()))
+            .collect::<Vec<_>>();
+        let mut prefixed = self
+            .namespaces
+            .iter()
+            .flatten()
+            .map(|(prefix, uri)| (prefix.to_string(), uri.value()))
+            .collect::<Vec<_>>();
+        prefixed.sort();
+        namespaces.extend(prefixed);
+
+        let namespaces = namespaces.iter().map(|(prefix, uri)| quote!((#prefix, #uri)));
+        Ok(quote! {
+            api_forge::XmlOptions::new()
+                .with_root(#root)
+                .with_namespaces(&[#(#namespaces),*])
+        })
+    }
+}
+
+/// Returns the `#[serde(rename = "...")]` of the struct, if any.
+fn serde_rename(attrs: &[syn::Attribute]) -> syn::Result<Option<String>> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        let items = attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+        )?;
+        for item in items {
+            if let syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(rename), .. }),
+                ..
+            }) = item
+            {
+                if path.is_ident("rename") {
+                    return Ok(Some(rename.value()));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The `retry` attribute: either an expression evaluating to a `RetryPolicy` or inline options.
+#[derive(Debug, Clone)]
+enum RetryArg {
+    Expr(syn::Expr),
+    Inline(RetryOptions),
+}
+
+#[derive(Debug, FromMeta, Clone)]
+struct RetryOptions {
+    #[darling(default)]
+    max_attempts: Option<u32>,
+    #[darling(default)]
+    base_delay_ms: Option<u64>,
+    #[darling(default)]
+    max_delay_ms: Option<u64>,
+    #[darling(default)]
+    jitter: Option<bool>,
+    #[darling(default)]
+    statuses: Option<Vec<u16>>,
+    #[darling(default)]
+    retry_non_idempotent: Option<bool>,
+}
+
+impl FromMeta for RetryArg {
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        RetryOptions::from_list(items).map(Self::Inline)
+    }
+
+    fn from_string(value: &str) -> darling::Result<Self> {
+        syn::parse_str(value)
+            .map(Self::Expr)
+            .map_err(|err| darling::Error::custom(format!("Failed to parse retry policy '{}': {}", value, err)))
+    }
+
+    fn from_expr(expr: &syn::Expr) -> darling::Result<Self> {
+        match expr {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(value), .. }) => Self::from_string(&value.value()),
+            expr => Ok(Self::Expr(expr.clone())),
+        }
+    }
+}
+
+impl RetryArg {
+    /// Generates an expression constructing the `RetryPolicy` in a const context.
+    fn to_policy(&self) -> proc_macro2::TokenStream {
+        match self {
+            Self::Expr(expr) => quote!(#expr),
+            Self::Inline(options) => {
+                let mut policy = quote!(api_forge::RetryPolicy::new());
+                if let Some(max_attempts) = options.max_attempts {
+                    policy = quote!(#policy.with_max_attempts(#max_attempts));
+                }
+                if let Some(base_delay_ms) = options.base_delay_ms {
+                    policy = quote!(#policy.with_base_delay(std::time::Duration::from_millis(#base_delay_ms)));
+                }
+                if let Some(max_delay_ms) = options.max_delay_ms {
+                    policy = quote!(#policy.with_max_delay(std::time::Duration::from_millis(#max_delay_ms)));
+                }
+                if let Some(jitter) = options.jitter {
+                    policy = quote!(#policy.with_jitter(#jitter));
+                }
+                if let Some(statuses) = &options.statuses {
+                    policy = quote!(#policy.with_retryable_statuses(&[#(#statuses),*]));
+                }
+                if let Some(retry_non_idempotent) = options.retry_non_idempotent {
+                    policy = quote!(#policy.with_retry_non_idempotent(#retry_non_idempotent));
+                }
+                policy
+            }
+        }
+    }
+}
+
+/// The `authentication` attribute: a method such as `Bearer`, or an API key with its location and
+/// name, such as `ApiKey(header = "X-Api-Key")`.
+#[derive(Debug, Clone)]
+enum AuthenticationArg {
+    Method(syn::Path),
+    ApiKey { location: Ident, name: LitStr },
+}
+
+impl FromMeta for AuthenticationArg {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        syn::parse_str::<syn::Expr>(value)
+            .map_err(|err| darling::Error::custom(format!("Failed to parse authentication '{}': {}", value, err)))
+            .and_then(|expr| Self::from_expr(&expr))
+    }
+
+    fn from_expr(expr: &syn::Expr) -> darling::Result<Self> {
+        match expr {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(value), .. }) => Self::from_string(&value.value()),
+            syn::Expr::Path(path) if path.path.is_ident("ApiKey") => Err(darling::Error::custom(
+                "`ApiKey` requires a location and name, e.g. `ApiKey(header = \"X-Api-Key\")`",
+            )
+            .with_span(expr)),
+            syn::Expr::Path(path) => Ok(Self::Method(path.path.clone())),
+            syn::Expr::Call(call) if matches!(&*call.func, syn::Expr::Path(func) if func.path.is_ident("ApiKey")) => {
+                let mut args = call.args.iter();
+                match (args.next(), args.next()) {
+                    (Some(arg), None) => Self::api_key(arg),
+                    _ => Err(darling::Error::custom(
+                        "`ApiKey` takes exactly one of `header`, `query` or `cookie`, e.g. `ApiKey(header = \"X-Api-Key\")`",
+                    )
+                    .with_span(expr)),
+                }
+            }
+            _ => Err(darling::Error::unexpected_expr_type(expr)),
+        }
+    }
+}
+
+impl AuthenticationArg {
+    /// Parses the `header = "..."`, `query = "..."` or `cookie = "..."` argument of `ApiKey`.
+    fn api_key(arg: &syn::Expr) -> darling::Result<Self> {
+        let syn::Expr::Assign(assign) = arg else {
+            return Err(darling::Error::custom("Expected `header`, `query` or `cookie` set to a name").with_span(arg));
+        };
+        let location = match &*assign.left {
+            syn::Expr::Path(path) if path.path.is_ident("header") => "Header",
+            syn::Expr::Path(path) if path.path.is_ident("query") => "Query",
+            syn::Expr::Path(path) if path.path.is_ident("cookie") => "Cookie",
+            left => {
+                return Err(darling::Error::custom("Unknown API key location, expected `header`, `query` or `cookie`")
+                    .with_span(left))
+            }
+        };
+        let name = match &*assign.right {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(name), .. }) => name.clone(),
+            right => return Err(darling::Error::custom("Expected the name as a string literal").with_span(right)),
+        };
+
+        let valid = match location {
+            // Header names are tokens as defined by RFC 9110.
+            "Header" => {
+                !name.value().is_empty()
+                    && name
+                        .value()
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+            }
+            _ => !name.value().is_empty(),
+        };
+        if !valid {
+            return Err(darling::Error::custom(format!("Invalid API key name: {}", name.value())).with_span(&name));
+        }
+
+        Ok(Self::ApiKey {
+            location: Ident::new(location, Span::call_site()),
+            name,
+        })
+    }
+
+    /// Generates the `AuthenticationMethod` of the request.
+    fn to_method(&self) -> proc_macro2::TokenStream {
+        match self {
+            Self::Method(path) => quote!(api_forge::AuthenticationMethod::#path),
+            Self::ApiKey { location, name } => quote! {
+                api_forge::AuthenticationMethod::ApiKey {
+                    location: api_forge::ApiKeyLocation::#location,
+                    name: #name,
+                }
+            },
+        }
+    }
 }
 
 #[derive(Debug, FromField, Clone)]
-#[darling(attributes(request))]
-struct HeaderField {
+#[darling(attributes(request), forward_attrs(serde))]
+struct RequestField {
     ident: Option<Ident>,
+    ty: syn::Type,
+    attrs: Vec<syn::Attribute>,
     #[darling(default)]
     header_name: Option<LitStr>,
+    #[darling(default)]
+    query: bool,
+    #[darling(default)]
+    body: bool,
+    #[darling(default)]
+    path: bool,
+    #[darling(default)]
+    cookie: Option<LitStr>,
+    #[darling(default)]
+    part: bool,
+    #[darling(default)]
+    file: bool,
+    #[darling(default)]
+    json_part: bool,
+    #[darling(default)]
+    part_name: Option<LitStr>,
+    #[darling(default)]
+    file_name: Option<LitStr>,
+    #[darling(default)]
+    mime: Option<LitStr>,
+}
+
+/// Where the value of a field is sent.
+#[derive(Debug, Clone, PartialEq)]
+enum Placement {
+    Query,
+    Body,
+    Path,
+    Header(LitStr),
+    Cookie(LitStr),
+}
+
+impl RequestField {
+    /// Resolves the placement of the field from its attributes, falling back to the placement of
+    /// the struct-level transmission method.
+    fn placement(&self, path_parameters: &[String], default: &Placement) -> syn::Result<Placement> {
+        let in_path_parameters = self
+            .ident
+            .as_ref()
+            .is_some_and(|ident| path_parameters.contains(&ident.to_string()));
+
+        let mut placements = vec![];
+        if self.query {
+            placements.push(Placement::Query);
+        }
+        if self.body {
+            placements.push(Placement::Body);
+        }
+        if self.part || self.file || self.json_part {
+            placements.push(Placement::Body);
+        }
+        if self.path || in_path_parameters {
+            placements.push(Placement::Path);
+        }
+        if let Some(header_name) = &self.header_name {
+            placements.push(Placement::Header(header_name.clone()));
+        }
+        if let Some(cookie) = &self.cookie {
+            placements.push(Placement::Cookie(cookie.clone()));
+        }
+
+        match placements.len() {
+            0 => Ok(default.clone()),
+            1 => Ok(placements.remove(0)),
+            _ => Err(SynError::new_spanned(
+                &self.ident,
+                "A field can only have one of `query`, `body`, `part`, `file`, `json_part`, `path`, `header_name` and `cookie`",
+            )),
+        }
+    }
+
+    /// Checks the multipart attributes of the field.
+    fn validate_multipart(&self, is_multipart: bool) -> syn::Result<()> {
+        let error = |message: &str| Err(SynError::new_spanned(&self.ident, message));
+
+        let has_multipart_attributes = self.part
+            || self.file
+            || self.json_part
+            || self.part_name.is_some()
+            || self.file_name.is_some()
+            || self.mime.is_some();
+        if has_multipart_attributes && !is_multipart {
+            return error("Multipart attributes require `transmission = Multipart`");
+        }
+        if [self.part, self.file, self.json_part].iter().filter(|set| **set).count() > 1 {
+            return error("A field can only have one of `part`, `file` and `json_part`");
+        }
+        if (self.file_name.is_some() || self.mime.is_some()) && !self.file {
+            return error("`file_name` and `mime` require `file`");
+        }
+        if let Some(mime) = &self.mime {
+            if !is_valid_mime(&mime.value()) {
+                return Err(SynError::new_spanned(mime, format!("Invalid mime type '{}'", mime.value())));
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates the code adding this field to the multipart `form`.
+    fn multipart_part(&self, ident: &Ident) -> proc_macro2::TokenStream {
+        let part_name = self
+            .part_name
+            .as_ref()
+            .map(LitStr::value)
+            .unwrap_or_else(|| ident.to_string());
+
+        let add_part = if self.file {
+            let file_name = match &self.file_name {
+                Some(file_name) => quote!(#file_name.to_string()),
+                None => quote! {
+                    api_forge::ToPart::file_name(value).unwrap_or_else(|| #part_name.to_string())
+                },
+            };
+            let mime = self.mime.as_ref().map(|mime| {
+                quote! {
+                    let part = part.mime_str(#mime).expect("mime type is validated by the derive");
+                }
+            });
+            quote! {
+                let part = api_forge::ToPart::to_part(value).file_name(#file_name);
+                #mime
+                form = form.part(#part_name, part);
+            }
+        } else if self.json_part {
+            quote! {
+                form = form.part(#part_name, api_forge::json_part(value)?);
+            }
+        } else {
+            quote! {
+                if let Some(part) = api_forge::text_part(value)? {
+                    form = form.part(#part_name, part);
+                }
+            }
+        };
+
+        optional_field_value(self, ident, add_part)
+    }
+}
+
+/// Returns true if the value is a valid mime type, e.g. `image/png` or `text/plain; charset=utf-8`.
+///
+/// Uses the parser of the `mime` crate, which reqwest uses for `Part::mime_str`, so every mime
+/// type accepted here is accepted when the part is built.
+fn is_valid_mime(mime: &str) -> bool {
+    mime.parse::<mime::Mime>().is_ok()
+}
+
+/// Wraps code using `value` so it only runs for `Some` if the field is an `Option`.
+fn optional_field_value(
+    field: &RequestField,
+    ident: &Ident,
+    code: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if is_option(&field.ty) {
+        quote! {
+            if let Some(value) = self.#ident.as_ref() {
+                #code
+            }
+        }
+    } else {
+        quote! {
+            let value = &self.#ident;
+            #code
+        }
+    }
+}
+
+/// Serde container attributes that can't be applied to the generated payload serializer.
+const UNSUPPORTED_PAYLOAD_ATTRIBUTES: &[&str] = &["remote", "transparent", "into", "from", "try_from"];
+
+/// Returns true if the type is written as an `Option`.
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// Copies the serde attributes of the request struct that apply to the payload serializer.
+fn payload_container_attributes(attrs: &[syn::Attribute]) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    let mut kept = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        let items = attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+        )?;
+        let items = items
+            .into_iter()
+            .filter(|item| {
+                !UNSUPPORTED_PAYLOAD_ATTRIBUTES
+                    .iter()
+                    .any(|name| item.path().is_ident(name))
+            })
+            .collect::<Vec<_>>();
+        if !items.is_empty() {
+            kept.push(quote!(#[serde(#(#items),*)]));
+        }
+    }
+    Ok(kept)
+}
+
+/// Generates an expression serializing only the given fields of the request.
+///
+/// The fields are serialized with a serde remote definition of the request struct that only lists
+/// them, so their serde attributes keep working. If all fields are included, the struct itself is
+/// serialized.
+fn payload_serializer(
+    input: &DeriveInput,
+    fields: &[RequestField],
+    included: &[&RequestField],
+) -> syn::Result<proc_macro2::TokenStream> {
+    if included.len() == fields.len() {
+        return Ok(quote!(self));
+    }
+
+    let name = &input.ident;
+    let remote = name.to_string();
+    let container_attributes = payload_container_attributes(&input.attrs)?;
+    let payload_fields = included.iter().map(|field| {
+        let RequestField { ident, ty, attrs, .. } = field;
+        quote! {
+            #(#attrs)*
+            #ident: #ty,
+        }
+    });
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    // Keeps all generic parameters in use, even if they only appear in excluded fields
+    let marker = (!generics.params.is_empty()).then(|| {
+        quote! {
+            #[serde(skip)]
+            __api_forge_marker: std::marker::PhantomData<fn() -> #name #ty_generics>,
+        }
+    });
+
+    let mut ref_generics = generics.clone();
+    ref_generics.params.insert(0, syn::parse_quote!('__api_forge));
+    let (ref_impl_generics, ref_ty_generics, _) = ref_generics.split_for_impl();
+
+    Ok(quote! {
+        &{
+            #[derive(serde::Serialize)]
+            #[serde(remote = #remote)]
+            #(#container_attributes)*
+            struct __ApiForgePayload #impl_generics #where_clause {
+                #(#payload_fields)*
+                #marker
+            }
+
+            struct __ApiForgePayloadRef #ref_impl_generics (&'__api_forge #name #ty_generics) #where_clause;
+
+            impl #ref_impl_generics serde::Serialize for __ApiForgePayloadRef #ref_ty_generics #where_clause {
+                fn serialize<__S: serde::Serializer>(&self, serializer: __S) -> Result<__S::Ok, __S::Error> {
+                    __ApiForgePayload::serialize(self.0, serializer)
+                }
+            }
+
+            __ApiForgePayloadRef(self)
+        }
+    })
 }
 
 /// # Request Derive Macro
@@ -63,9 +569,17 @@ struct HeaderField {
 /// ### Optional Attributes
 ///
 /// - `response_type` (optional): The type to deserialize the API response into.
-///   Defaults to `EmptyResponse` which is interpreted as `()`.
+///   Defaults to `()`. Empty bodies and `204 No Content` responses are only accepted by `()`,
+///   `Option<T>` and `api_forge::EmptyResponse`; use `EmptyResponse` to ignore whatever body
+///   the endpoint returns.
 ///   Example: `#[request(endpoint = "/api/users", response_type = "Vec<User>")]`
 ///
+/// - `error_type` (optional): The type error response bodies are deserialized into, returned as
+///   `ApiForgeError::Api`. Defaults to the client's error type. `application/problem+json` bodies
+///   are returned as `ApiForgeError::Problem` instead, and error bodies that don't parse are
+///   returned as `ApiForgeError::ResponseError` with the raw text.
+///   Example: `#[request(endpoint = "/api/users", error_type = "MyApiError")]`
+///
 /// - `method` (optional): The HTTP method to use. Defaults to `GET`.
 ///   Supported values: `GET`, `POST`, `PUT`, `DELETE`, `PATCH`, `HEAD`.
 ///   Example: `#[request(endpoint = "/api/users", method = "POST")]`
@@ -75,15 +589,33 @@ struct HeaderField {
 ///   - `QueryParams`: Sends data as URL query parameters
 ///   - `Json`: Sends data as a JSON body
 ///   - `FormData`: Sends data as application/x-www-form-urlencoded
-///   - `Multipart`: Sends data as multipart/form-data (requires implementing `multipart_form_data()`)
+///   - `Multipart`: Sends data as multipart/form-data, built from the part fields
+///   - `Stream`: Sends the `#[request(body)]` field, an `api_forge::StreamBody`, as the body
+///     without buffering it. The other fields are sent as query parameters.
+///   - `Xml`: Sends data as an `application/xml` body, serialized with `serde_xml_rust`
+///   - `Raw`: Sends the `#[request(body)]` field verbatim as the body. The field must implement
+///     `api_forge::RawBody`, e.g. `Vec<u8>`, `Bytes` or `String`, which also picks the
+///     `Content-Type` unless `content_type` is set. The other fields are sent as query parameters.
+///   - `MessagePack`: Sends data as an `application/msgpack` body. Requires the `msgpack` feature.
+///   - `Cbor`: Sends data as an `application/cbor` body. Requires the `cbor` feature.
+///   - `Protobuf`: Sends the `#[request(body)]` field, a `prost::Message`, as an
+///     `application/x-protobuf` body. The other fields are sent as query parameters.
+///     Requires the `protobuf` feature.
+///
 ///   Example: `#[request(endpoint = "/api/users", transmission = "Json")]`
 ///
 /// - `authentication` (optional): The authentication method to use. Defaults to `None`.
+///   The credentials are applied by the `AuthProvider` of the client or call when sending.
 ///   Supported values:
-///   - `None`: No authentication
+///   - `None`: No authentication, credentials are never sent
 ///   - `Basic`: HTTP Basic authentication
 ///   - `Bearer`: Bearer token authentication
-///   Example: `#[request(endpoint = "/api/users", authentication = "Bearer")]`
+///   - `Digest`: HTTP Digest authentication
+///   - `ApiKey(header = "...")`, `ApiKey(query = "...")` or `ApiKey(cookie = "...")`: An API
+///     key sent as the named header, query parameter or cookie
+///
+///   Example: `#[request(endpoint = "/api/users", authentication = "Bearer")]` or
+///   `#[request(endpoint = "/api/users", authentication = ApiKey(header = "X-Api-Key"))]`
 ///
 /// - `path_parameters` (optional): A list of field names that should be used to replace
 ///   placeholders in the endpoint path.
@@ -97,11 +629,66 @@ struct HeaderField {
 ///   may add one based on the transmission method).
 ///   Example: `#[request(endpoint = "/api/users", content_type = "application/vnd.api+json")]`
 ///
+/// - `retry` (optional): The retry policy for this request, overriding the client's policy.
+///   Either an expression evaluating to an `api_forge::RetryPolicy` in a const context, or inline
+///   options: `max_attempts`, `base_delay_ms`, `max_delay_ms`, `jitter`, `statuses` and
+///   `retry_non_idempotent`. Unset options keep the `RetryPolicy::new()` defaults.
+///   Example: `#[request(endpoint = "/api/users", retry = MY_POLICY)]`
+///   Example: `#[request(endpoint = "/api/users", retry(max_attempts = 5, statuses = [429, 503]))]`
+///
+/// - `xml` (optional): The root element and namespace declarations of `Xml` request bodies.
+///   The root element defaults to the struct name, or its `#[serde(rename)]`. `namespace` sets
+///   the default namespace, `namespaces` adds prefixed ones.
+///   Example: `#[request(endpoint = "/api/orders", transmission = "Xml", xml(root = "Order", namespace = "urn:orders", namespaces(xsi = "http://www.w3.org/2001/XMLSchema-instance")))]`
+///
+/// - `multipart_form` (optional): A function `fn(&Self) -> reqwest::multipart::Form` building the
+///   form of a `Multipart` request by hand instead of from the fields.
+///   Example: `#[request(endpoint = "/api/upload", transmission = "Multipart", multipart_form = "build_form")]`
+///
 /// ## Field-Level Attributes
 ///
 /// - `header_name`: Marks a field to be sent as an HTTP header instead of as part of the request data.
+///   `Option` fields are only sent if they are `Some`.
 ///   Example: `#[request(header_name = "X-Api-Key")]`
 ///
+/// - `query`: Sends the field as a query parameter, whatever the transmission method.
+///   Example: `#[request(query)]`
+///
+/// - `body`: Sends the field in the body using the transmission method. Body fields of
+///   `QueryParams` requests are sent as JSON. For `Stream` and `Raw` requests, this marks
+///   the field sent as the body.
+///   Example: `#[request(body)]`
+///
+/// - `path`: Replaces the `{field_name}` placeholder of the endpoint with the field, like
+///   `path_parameters`.
+///   Example: `#[request(path)]`
+///
+/// - `cookie`: Sends the field as a cookie with the given name. `Option` fields are only sent
+///   if they are `Some`.
+///   Example: `#[request(cookie = "session")]`
+///
+/// - `part`: Sends the field as a text part of a `Multipart` request. Strings are sent as is, other
+///   values as JSON, e.g. `42`. This is the default for body fields of `Multipart` requests.
+///   Example: `#[request(part)]`
+///
+/// - `file`: Sends the field as a file part of a `Multipart` request. The field must implement
+///   `api_forge::ToPart`, e.g. `PathBuf` (streamed from disk), `Vec<u8>`, `Bytes` or `StreamBody`.
+///   `file_name` and `mime` set the file name and content type of the part.
+///   Example: `#[request(file, file_name = "avatar.png", mime = "image/png")]`
+///
+/// - `json_part`: Sends the field as an `application/json` part of a `Multipart` request.
+///   Example: `#[request(json_part)]`
+///
+/// - `part_name`: The name of a multipart part, defaulting to the field name.
+///   Example: `#[request(part, part_name = "userName")]`
+///
+/// A `Multipart` request without any part fields is a compile error, unless it sets `multipart_form`.
+///
+/// Fields without one of these attributes are sent as query parameters for `QueryParams`,
+/// `Stream` and `Raw` requests and in the body otherwise. Fields used as path parameters, headers or cookies are
+/// not serialized with the data transmission method. The remaining fields keep their serde
+/// attributes, as do the struct-level attributes such as `rename_all`.
+///
 /// ## Complete Examples
 ///
 /// ### Simple GET Request
@@ -170,6 +757,23 @@ struct HeaderField {
 /// }
 /// ```
 ///
+/// ### Mixing Query Parameters and a JSON Body
+///
+/// ```no_compile
+/// #[derive(Debug, Serialize, Request)]
+/// #[request(endpoint = "/api/items/{id}", method = "PUT", transmission = "Json")]
+/// struct UpdateItemRequest {
+///     #[request(path)]
+///     id: u64,
+///     #[request(query)]
+///     dry_run: bool,
+///     #[request(cookie = "session")]
+///     session: String,
+///     // Sent in the JSON body
+///     name: String,
+/// }
+/// ```
+///
 /// ### Multipart File Upload
 ///
 /// ```no_compile
@@ -180,21 +784,16 @@ struct HeaderField {
 ///     transmission = "Multipart"
 /// )]
 /// struct UploadFileRequest {
+///     // Text parts, `None` is skipped
 ///     name: String,
 ///     description: Option<String>,
-/// }
 ///
-/// // When using Multipart, you must implement this method
-/// impl UploadFileRequest {
-///     fn multipart_form_data(&self) -> reqwest::multipart::Form {
-///         let file_bytes = std::fs::read("path/to/file.jpg").unwrap();
-///         let part = reqwest::multipart::Part::bytes(file_bytes)
-///             .file_name("file.jpg");
-///         
-///         reqwest::multipart::Form::new()
-///             .text("name", self.name.clone())
-///             .part("file", part)
-///     }
+///     // Streamed from disk when the request is sent
+///     #[request(file, mime = "image/jpeg")]
+///     file: PathBuf,
+///
+///     #[request(json_part)]
+///     metadata: Metadata,
 /// }
 /// ```
 ///
@@ -204,7 +803,7 @@ struct HeaderField {
 /// specified response type. This implementation:
 ///
 /// 1. Builds the complete URL by combining the base URL with the endpoint and replacing any path parameters
-/// 2. Creates an HTTP request with the specified method
+/// 2. Creates an HTTP request with the specified method on the provided `reqwest::Client`
 /// 3. Applies the data transmission method (query params, JSON, form data, or multipart)
 /// 4. Adds authentication if provided
 /// 5. Sets all specified headers
@@ -220,7 +819,7 @@ struct HeaderField {
 /// ## Type Requirements
 ///
 /// - Your struct must be serializable (implement `Serialize` from serde)
-/// - For `Multipart` transmission, your struct must implement a `multipart_form_data()` method
+/// - For `Multipart` transmission, `file` fields must implement `api_forge::ToPart`
 /// - Response types must be deserializable (implement `Deserialize` from serde)
 #[proc_macro_derive(Request, attributes(request))]
 pub fn derive_request(input: TokenStream) -> TokenStream {
@@ -239,27 +838,8 @@ pub fn derive_request(input: TokenStream) -> TokenStream {
     };
 
     let name = &input.ident;
-    let data = args.data.clone();
-    let mut header_inserts = vec![];
-
-    data.map_struct_fields(|field| {
-        if let Some(header_name) = &field.header_name {
-            if let Some(header_field_ident) = &field.ident {
-                let header_name_value = header_name.value();
-
-                let header_insert = quote! {
-                    if let Some(value) = self.#header_field_ident.as_ref() {
-                        all_headers.insert(#header_name_value, reqwest::header::HeaderValue::from_str(&value.to_string()).unwrap_or_default());
-                    }
-                };
-
-                header_inserts.push(header_insert);
-            }
-        }
-    });
-
+    let fields = args.data.clone().take_struct().map(|fields| fields.fields).unwrap_or_default();
     let endpoint = args.endpoint;
-    // Create a path for EmptyResponse
     let response_type = match &args.response_type {
         Some(type_str) => {
             // Parse the type string into a syn::Type
@@ -275,9 +855,23 @@ pub fn derive_request(input: TokenStream) -> TokenStream {
                 }
             }
         }
-        None => {
-            syn::parse_str::<syn::Type>("EmptyResponse").unwrap()
-        }
+        None => syn::parse_quote!(()),
+    };
+    let error_type = match &args.error_type {
+        Some(type_str) => match syn::parse_str::<syn::Type>(type_str) {
+            Ok(parsed_type) => Some(quote! {
+                const ERROR_TYPE: Option<api_forge::ErrorType> = Some(api_forge::ErrorType::of::<#parsed_type>());
+            }),
+            Err(err) => {
+                return SynError::new(
+                    Span::call_site(),
+                    format!("Failed to parse error_type '{}': {}", type_str, err)
+                )
+                    .to_compile_error()
+                    .into();
+            }
+        },
+        None => None,
     };
     let method = args.method.clone().unwrap_or_else(|| syn::parse_quote!(GET));
     let transmission_method = args
@@ -286,20 +880,295 @@ pub fn derive_request(input: TokenStream) -> TokenStream {
         .unwrap_or_else(|| syn::parse_quote!(QueryParams));
     let authentication_method = args
         .authentication
-        .clone()
-        .unwrap_or_else(|| syn::parse_quote!(None));
+        .as_ref()
+        .map(AuthenticationArg::to_method)
+        .unwrap_or_else(|| quote!(api_forge::AuthenticationMethod::None));
+
+    let transmission_name = transmission_method
+        .segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+    // Fields without a placement attribute follow the struct-level transmission method. The body
+    // of `Stream` and `Raw` requests is a single field, so the other fields are sent as query
+    // parameters.
+    let has_body_field = matches!(transmission_name.as_str(), "Stream" | "Raw" | "Protobuf");
+    let default_placement = if transmission_name == "QueryParams" || has_body_field {
+        Placement::Query
+    } else {
+        Placement::Body
+    };
 
     // Handle path parameters more robustly
-    let path_parameters = args.path_parameters.unwrap_or_default();
-    let path_parameters_str = path_parameters
+    let path_parameters = args
+        .path_parameters
+        .unwrap_or_default()
         .iter()
         .map(|p| p.value())
         .collect::<Vec<_>>();
+
+    let is_multipart = transmission_name == "Multipart";
+    if args.xml.is_some() && transmission_name != "Xml" {
+        return SynError::new(Span::call_site(), "`xml` requires `transmission = Xml`")
+            .to_compile_error()
+            .into();
+    }
+    let mut placements = vec![];
+    for field in &fields {
+        let placement = field
+            .validate_multipart(is_multipart)
+            .and_then(|_| field.placement(&path_parameters, &default_placement));
+        match placement {
+            Ok(placement) => placements.push(placement),
+            Err(err) => return err.to_compile_error().into(),
+        }
+    }
+
+    let mut path_parameters_str = path_parameters.clone();
+    let mut query_fields = vec![];
+    let mut body_fields = vec![];
+    let mut header_inserts = vec![];
+    let mut cookie_inserts = vec![];
+
+    for (field, placement) in fields.iter().zip(&placements) {
+        let Some(ident) = &field.ident else {
+            // Fields of tuple structs can't be placed individually
+            match placement {
+                Placement::Query => query_fields.push(field),
+                _ => body_fields.push(field),
+            }
+            continue;
+        };
+
+        match placement {
+            Placement::Query => query_fields.push(field),
+            Placement::Body => body_fields.push(field),
+            Placement::Path => {
+                let ident_str = ident.to_string();
+                if !endpoint.contains(&format!("{{{}}}", ident_str)) {
+                    return SynError::new_spanned(
+                        ident,
+                        format!("Endpoint '{}' has no `{{{}}}` placeholder for this path field", endpoint, ident_str),
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+                if !path_parameters_str.contains(&ident_str) {
+                    path_parameters_str.push(ident_str);
+                }
+            }
+            Placement::Header(header_name) => {
+                let header_name_value = header_name.value();
+                let insert = quote! {
+                    match reqwest::header::HeaderValue::from_str(&value.to_string()) {
+                        Ok(value) => {
+                            all_headers.insert(#header_name_value, value);
+                        }
+                        Err(err) => tracing::warn!("Skipping invalid value for header {}: {}", #header_name_value, err),
+                    }
+                };
+                header_inserts.push(optional_field_value(field, ident, insert));
+            }
+            Placement::Cookie(cookie_name) => {
+                let cookie_name_value = cookie_name.value();
+                let insert = quote! {
+                    cookies.push(format!("{}={}", #cookie_name_value, value));
+                };
+                cookie_inserts.push(optional_field_value(field, ident, insert));
+            }
+        }
+    }
+
     let path_parameters_idents = path_parameters_str
         .iter()
         .map(|p| Ident::new(p, Span::call_site()))
         .collect::<Vec<_>>();
 
+    // Fields for the default placement are always sent, the others only if there are any
+    let query = if default_placement == Placement::Query || !query_fields.is_empty() {
+        match payload_serializer(&input, &fields, &query_fields) {
+            Ok(payload) => Some(quote! {
+                let builder = builder.query(#payload);
+            }),
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        None
+    };
+
+    let body = if has_body_field {
+        let body_field = match body_fields.as_slice() {
+            [field] => field.ident.as_ref(),
+            _ => None,
+        };
+        let Some(body_field) = body_field else {
+            return SynError::new(
+                Span::call_site(),
+                format!("{} requests need exactly one `#[request(body)]` field", transmission_name),
+            )
+            .to_compile_error()
+            .into();
+        };
+        if transmission_name == "Stream" {
+            Some(quote! {
+                let builder = builder.body(api_forge::StreamBody::to_body(&self.#body_field));
+            })
+        } else if transmission_name == "Protobuf" {
+            // An explicit `content_type` is set with the other headers
+            let protobuf_content_type = args.content_type.is_none().then(|| {
+                quote! {
+                    let builder = builder.header(reqwest::header::CONTENT_TYPE, api_forge::PROTOBUF_CONTENT_TYPE);
+                }
+            });
+            Some(quote! {
+                let builder = builder.body(api_forge::protobuf_body(&self.#body_field));
+                #protobuf_content_type
+            })
+        } else {
+            // An explicit `content_type` is set with the other headers
+            let raw_content_type = args.content_type.is_none().then(|| {
+                quote! {
+                    let builder = builder.header(
+                        reqwest::header::CONTENT_TYPE,
+                        api_forge::RawBody::content_type(&self.#body_field),
+                    );
+                }
+            });
+            Some(quote! {
+                let builder = builder.body(api_forge::RawBody::to_body(&self.#body_field));
+                #raw_content_type
+            })
+        }
+    } else if default_placement == Placement::Body || !body_fields.is_empty() {
+        let payload = match payload_serializer(&input, &fields, &body_fields) {
+            Ok(payload) => payload,
+            Err(err) => return err.to_compile_error().into(),
+        };
+        match transmission_name.as_str() {
+            "FormData" => Some(quote!(let builder = builder.form(#payload);)),
+            "Xml" => {
+                let options = match args.xml.clone().unwrap_or_default().to_options(&input) {
+                    Ok(options) => options,
+                    Err(err) => return err.to_compile_error().into(),
+                };
+                // An explicit `content_type` is set with the other headers
+                let xml_content_type = args.content_type.is_none().then(|| {
+                    quote! {
+                        let builder = builder.header(reqwest::header::CONTENT_TYPE, "application/xml");
+                    }
+                });
+                Some(quote! {
+                    const XML_OPTIONS: api_forge::XmlOptions = #options;
+                    let builder = builder.body(api_forge::xml_body(#payload, &XML_OPTIONS)?);
+                    #xml_content_type
+                })
+            }
+            "Multipart" => Some(quote!(let builder = builder.multipart(self.multipart_form_data()?);)),
+            "MessagePack" | "Cbor" => {
+                let (encode, content_type) = if transmission_name == "MessagePack" {
+                    (quote!(api_forge::msgpack_body), quote!(api_forge::MSGPACK_CONTENT_TYPE))
+                } else {
+                    (quote!(api_forge::cbor_body), quote!(api_forge::CBOR_CONTENT_TYPE))
+                };
+                // An explicit `content_type` is set with the other headers
+                let binary_content_type = args.content_type.is_none().then(|| {
+                    quote! {
+                        let builder = builder.header(reqwest::header::CONTENT_TYPE, #content_type);
+                    }
+                });
+                Some(quote! {
+                    let builder = builder.body(#encode(#payload)?);
+                    #binary_content_type
+                })
+            }
+            // Body fields of `QueryParams` requests are sent as JSON
+            _ => Some(quote!(let builder = builder.json(#payload);)),
+        }
+    } else {
+        None
+    };
+
+    let multipart_form_data = match (&args.multipart_form, is_multipart) {
+        (Some(multipart_form), true) => Some(quote! {
+            fn multipart_form_data(&self) -> api_forge::ApiResult<reqwest::multipart::Form> {
+                Ok(#multipart_form(self))
+            }
+        }),
+        (Some(multipart_form), false) => {
+            return SynError::new_spanned(multipart_form, "`multipart_form` requires `transmission = Multipart`")
+                .to_compile_error()
+                .into();
+        }
+        (None, true) => {
+            let parts = body_fields
+                .iter()
+                .filter_map(|field| field.ident.as_ref().map(|ident| field.multipart_part(ident)))
+                .collect::<Vec<_>>();
+            if parts.is_empty() {
+                return SynError::new(
+                    Span::call_site(),
+                    "Multipart requests need at least one part field, or a `multipart_form` function building the form",
+                )
+                .to_compile_error()
+                .into();
+            }
+            Some(quote! {
+                fn multipart_form_data(&self) -> api_forge::ApiResult<reqwest::multipart::Form> {
+                    let mut form = reqwest::multipart::Form::new();
+                    #(#parts)*
+                    Ok(form)
+                }
+            })
+        }
+        (None, false) => None,
+    };
+
+    // Stream bodies and `StreamBody` parts can only be sent once
+    let is_replayable = if transmission_name == "Stream" {
+        Some(quote! {
+            fn is_replayable(&self) -> bool {
+                false
+            }
+        })
+    } else if is_multipart && args.multipart_form.is_none() {
+        let checks = body_fields
+            .iter()
+            .filter(|field| field.file)
+            .filter_map(|field| {
+                field.ident.as_ref().map(|ident| {
+                    optional_field_value(field, ident, quote!(replayable &= api_forge::ToPart::is_replayable(value);))
+                })
+            })
+            .collect::<Vec<_>>();
+        (!checks.is_empty()).then(|| {
+            quote! {
+                fn is_replayable(&self) -> bool {
+                    let mut replayable = true;
+                    #(#checks)*
+                    replayable
+                }
+            }
+        })
+    } else {
+        None
+    };
+
+    let cookies = (!cookie_inserts.is_empty()).then(|| {
+        quote! {
+            let mut cookies = Vec::<String>::new();
+            if let Some(cookie) = all_headers.get(reqwest::header::COOKIE).and_then(|value| value.to_str().ok()) {
+                cookies.push(cookie.to_string());
+            }
+            #(#cookie_inserts)*
+            match reqwest::header::HeaderValue::from_str(&cookies.join("; ")) {
+                Ok(value) => {
+                    all_headers.insert(reqwest::header::COOKIE, value);
+                }
+                Err(err) => tracing::warn!("Skipping invalid cookies: {}", err),
+            }
+        }
+    });
+
     // Content type headers
     let accept = args.accept.unwrap_or_else(||
         LitStr::new("application/json", Span::call_site())
@@ -311,16 +1180,6 @@ pub fn derive_request(input: TokenStream) -> TokenStream {
         }
     });
 
-    // Define empty_type as a path to EmptyResponse for comparison
-    let empty_type = "EmptyResponse".to_string();
-
-    // If response_type is EmptyResponse, use (), otherwise use the specified type
-    let res_type = if std::format!("{}", quote!(#response_type)) == std::format!("{}", quote!(#empty_type)) {
-        quote!(())
-    } else {
-        quote!(#response_type)
-    };
-
     // Generate a more efficient method match
     let method_match = quote! {
         let builder = match Self::METHOD {
@@ -351,6 +1210,13 @@ pub fn derive_request(input: TokenStream) -> TokenStream {
         }
     };
 
+    let retry_policy = args.retry.as_ref().map(|retry| {
+        let policy = retry.to_policy();
+        quote! {
+            const RETRY_POLICY: Option<api_forge::RetryPolicy> = Some(#policy);
+        }
+    });
+
     // Extract the input's generics to reuse them in the impl
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
@@ -362,39 +1228,31 @@ pub fn derive_request(input: TokenStream) -> TokenStream {
 
     // Generate the final code for the derive macro
     let expanded = quote! {
-        impl #impl_generics api_forge::ApiRequest<#res_type> for #name #ty_generics #where_clause {
+        impl #impl_generics api_forge::ApiRequest<#response_type> for #name #ty_generics #where_clause {
             const ENDPOINT: &'static str = #endpoint;
             const METHOD: reqwest::Method = reqwest::Method::#method;
             const DATA_TRANSMISSION_METHOD: api_forge::DataTransmissionMethod = api_forge::DataTransmissionMethod::#transmission_method;
-            const AUTHENTICATION_METHOD: api_forge::AuthenticationMethod = api_forge::AuthenticationMethod::#authentication_method;
+            const AUTHENTICATION_METHOD: api_forge::AuthenticationMethod = #authentication_method;
+            #retry_policy
+            #error_type
+
+            #multipart_form_data
+            #is_replayable
 
             fn generate_request(
                 &self,
+                client: &reqwest::Client,
                 base_url: &str,
                 headers: Option<reqwest::header::HeaderMap>,
-                token: Option<(String, Option<String>)>,
-            ) -> reqwest::RequestBuilder {
+            ) -> api_forge::ApiResult<reqwest::RequestBuilder> {
                 #path_param_replacements
 
-                let client = reqwest::Client::new();
                 #method_match
 
                 // Apply data transmission method
-                let mut builder = match Self::DATA_TRANSMISSION_METHOD {
-                    api_forge::DataTransmissionMethod::QueryParams => builder.query(self),
-                    api_forge::DataTransmissionMethod::Json => builder.json(self),
-                    api_forge::DataTransmissionMethod::FormData => builder.form(self),
-                    api_forge::DataTransmissionMethod::Multipart => builder.multipart(self.multipart_form_data()),
-                };
-
-                // Apply authentication if provided
-                if let Some((token, password)) = token {
-                    builder = match Self::AUTHENTICATION_METHOD {
-                        api_forge::AuthenticationMethod::Basic => builder.basic_auth(token, password),
-                        api_forge::AuthenticationMethod::Bearer => builder.bearer_auth(token),
-                        api_forge::AuthenticationMethod::None => builder,
-                    };
-                }
+                #query
+                #body
+                let mut builder = builder;
 
                 // Create and populate headers
                 let mut all_headers = reqwest::header::HeaderMap::new();
@@ -407,6 +1265,9 @@ pub fn derive_request(input: TokenStream) -> TokenStream {
                     all_headers.extend(headers);
                 }
 
+                // Add cookies from fields
+                #cookies
+
                 // Set default headers if not already set
                 if !all_headers.contains_key(reqwest::header::ACCEPT) {
                     all_headers.insert(
@@ -420,10 +1281,28 @@ pub fn derive_request(input: TokenStream) -> TokenStream {
                 builder = builder.headers(all_headers);
 
                 tracing::debug!("Generated request: {:?}", builder);
-                builder
+                Ok(builder)
             }
         }
     };
 
     TokenStream::from(expanded)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::is_valid_mime;
+
+    #[test]
+    fn accepts_valid_mime_types() {
+        for mime in ["image/png", "text/plain; charset=utf-8", "application/vnd.api+json", "text/plain; format=\"a b\""] {
+            assert!(is_valid_mime(mime), "{} should be valid", mime);
+        }
+    }
+
+    #[test]
+    fn rejects_mime_types_reqwest_rejects() {
+        for mime in ["image", "text/plain; charset=utf 8", "text/plain; charset", "image/png x"] {
+            assert!(!is_valid_mime(mime), "{} should be invalid", mime);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use serde::{Serialize, Deserialize};
-use api_forge::ApiRequest;
+use api_forge::ApiClient;
 use api_forge_macro::Request;
 
 const BASE_URL: &str = "https://jsonplaceholder.typicode.com";
@@ -26,12 +26,13 @@ pub struct Posts {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
 	
+	let client = ApiClient::new(BASE_URL);
 	let request = GetPosts;
 
-	let res = request.send_request(BASE_URL, None, None).await;
+	let res = client.execute(&request).await;
 
 	match res {
-		Ok(posts) => println!("{:?}", posts.json::<Vec<Posts>>().await?),
+		Ok(posts) => println!("{:?}", posts),
 		Err(e) => panic!("{:?}", e),
 	}
 	
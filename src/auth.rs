@@ -0,0 +1,407 @@
+use crate::error::ApiForgeError;
+use crate::middleware::Next;
+use crate::traits::AuthenticationMethod;
+use crate::ApiResult;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE};
+use reqwest::StatusCode;
+use std::fmt;
+use tracing::debug;
+
+/// Applies credentials to outgoing requests.
+///
+/// Providers are set on a client with `ApiClientBuilder::auth`, or passed to the `send_*`
+/// methods of `ApiRequest`. They run on the fully built request, before any middleware, once
+/// per attempt when the request is retried. Requests with `AuthenticationMethod::None` are sent
+/// without credentials.
+///
+/// If a request is rejected with `401 Unauthorized`, `on_unauthorized` can renew the credentials
+/// to have the request sent once more.
+///
+/// On `wasm32`, the returned futures are not `Send`, so implementations use
+/// `#[async_trait(?Send)]` there.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{async_trait, ApiClient, ApiResult, AuthProvider, AuthenticationMethod};
+/// use reqwest::header::HeaderValue;
+///
+/// /// Sends a session token read from the environment.
+/// struct SessionAuth;
+///
+/// #[async_trait]
+/// impl AuthProvider for SessionAuth {
+///     async fn apply(&self, request: &mut reqwest::Request, _method: AuthenticationMethod) -> ApiResult<()> {
+///         let token = std::env::var("SESSION_TOKEN").unwrap_or_default();
+///         let value = HeaderValue::from_str(&token).map_err(api_forge::ApiForgeError::auth)?;
+///         request.headers_mut().insert("X-Session", value);
+///         Ok(())
+///     }
+/// }
+///
+/// let client = ApiClient::builder("https://api.example.com")
+///     .auth(SessionAuth)
+///     .build();
+/// ```
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait AuthProvider: Send + Sync + 'static {
+    /// Adds the credentials to the request.
+    ///
+    /// `method` is the `AUTHENTICATION_METHOD` of the request, never `AuthenticationMethod::None`.
+    async fn apply(&self, request: &mut reqwest::Request, method: AuthenticationMethod) -> ApiResult<()>;
+
+    /// Called when a request was rejected with `401 Unauthorized`.
+    ///
+    /// `headers` are the headers of the rejected response, such as its `WWW-Authenticate`
+    /// challenge. Returns `true` if the credentials were renewed, in which case the request is
+    /// generated, passed to `apply` and sent once more. The default doesn't retry.
+    async fn on_unauthorized(&self, method: AuthenticationMethod, headers: &HeaderMap) -> ApiResult<bool> {
+        let _ = (method, headers);
+        Ok(false)
+    }
+
+    /// Adds the credentials to a request sent by an `ApiClient`.
+    ///
+    /// `next` sends requests through the client's middleware and transport, for providers that
+    /// make requests of their own, such as `OAuth2Auth` fetching tokens. The default calls `apply`.
+    async fn apply_with(
+        &self,
+        request: &mut reqwest::Request,
+        method: AuthenticationMethod,
+        next: Next<'_>,
+    ) -> ApiResult<()> {
+        let _ = next;
+        self.apply(request, method).await
+    }
+
+    /// Called when a request was rejected with `401 Unauthorized`, with the headers it was sent
+    /// with.
+    ///
+    /// `sent` are the headers of the rejected request, including the credentials `apply` added,
+    /// so providers can tell whether those credentials were renewed in the meantime. The default
+    /// calls `on_unauthorized`.
+    async fn on_rejected(
+        &self,
+        method: AuthenticationMethod,
+        sent: &HeaderMap,
+        headers: &HeaderMap,
+    ) -> ApiResult<bool> {
+        let _ = sent;
+        self.on_unauthorized(method, headers).await
+    }
+}
+
+/// Applies the provider to the request, unless the request doesn't use authentication.
+///
+/// `next` is the middleware chain of the client sending the request, if any.
+pub(crate) async fn authenticate(
+    request: &mut reqwest::Request,
+    auth: Option<&dyn AuthProvider>,
+    method: AuthenticationMethod,
+    next: Option<Next<'_>>,
+) -> ApiResult<()> {
+    match (auth, next) {
+        (Some(auth), Some(next)) if method != AuthenticationMethod::None => auth.apply_with(request, method, next).await,
+        (Some(auth), None) if method != AuthenticationMethod::None => auth.apply(request, method).await,
+        (Some(_), _) => {
+            debug!("Request uses no authentication, skipping credentials");
+            Ok(())
+        }
+        (None, _) => Ok(()),
+    }
+}
+
+/// Returns whether a response rejected the credentials and the provider renewed them, so the
+/// request should be sent once more.
+///
+/// `sent` are the headers the request was sent with.
+pub(crate) async fn renew_credentials(
+    auth: Option<&dyn AuthProvider>,
+    method: AuthenticationMethod,
+    sent: &HeaderMap,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> ApiResult<bool> {
+    match auth {
+        Some(auth) if status == StatusCode::UNAUTHORIZED && method != AuthenticationMethod::None => {
+            let renewed = auth.on_rejected(method, sent, headers).await?;
+            if renewed {
+                debug!("Credentials were rejected and renewed, sending the request again");
+            }
+            Ok(renewed)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Fails unless the request uses the authentication method the provider sends credentials for.
+pub(crate) fn expect_method(expected: AuthenticationMethod, method: AuthenticationMethod) -> ApiResult<()> {
+    if method == expected {
+        return Ok(());
+    }
+    Err(ApiForgeError::auth(format!(
+        "The request uses {:?} authentication, but the provider sends {:?} credentials",
+        method, expected
+    )))
+}
+
+/// Encodes bytes as lowercase hex.
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Creates a header value that is hidden from `Debug` output.
+pub(crate) fn sensitive_value(value: &str) -> ApiResult<HeaderValue> {
+    let mut value = HeaderValue::from_str(value).map_err(ApiForgeError::auth)?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// Sends a token in the `Authorization: Bearer` header.
+///
+/// Requests that use another authentication method than `Bearer` fail with an `AuthError`.
+#[derive(Clone)]
+pub struct BearerAuth {
+    token: String,
+}
+
+impl BearerAuth {
+    /// Creates a provider sending the token.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self { token: token.into() }
+    }
+}
+
+impl fmt::Debug for BearerAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BearerAuth").finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl AuthProvider for BearerAuth {
+    async fn apply(&self, request: &mut reqwest::Request, method: AuthenticationMethod) -> ApiResult<()> {
+        expect_method(AuthenticationMethod::Bearer, method)?;
+        let value = sensitive_value(&format!("Bearer {}", self.token))?;
+        request.headers_mut().insert(AUTHORIZATION, value);
+        Ok(())
+    }
+}
+
+/// Sends a username and optional password with HTTP Basic authentication.
+///
+/// Requests that use another authentication method than `Basic` fail with an `AuthError`.
+#[derive(Clone)]
+pub struct BasicAuth {
+    username: String,
+    password: Option<String>,
+}
+
+impl BasicAuth {
+    /// Creates a provider sending the username and password.
+    pub fn new(username: impl Into<String>, password: Option<String>) -> Self {
+        Self {
+            username: username.into(),
+            password,
+        }
+    }
+}
+
+impl fmt::Debug for BasicAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl AuthProvider for BasicAuth {
+    async fn apply(&self, request: &mut reqwest::Request, method: AuthenticationMethod) -> ApiResult<()> {
+        expect_method(AuthenticationMethod::Basic, method)?;
+        let credentials = format!("{}:{}", self.username, self.password.as_deref().unwrap_or_default());
+        let value = sensitive_value(&format!("Basic {}", STANDARD.encode(credentials)))?;
+        request.headers_mut().insert(AUTHORIZATION, value);
+        Ok(())
+    }
+}
+
+/// Where an API key is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeyLocation {
+    /// A request header.
+    Header,
+    /// A query parameter.
+    Query,
+    /// A cookie.
+    Cookie,
+}
+
+/// Adds an API key to a request as a header, query parameter or cookie.
+pub(crate) fn apply_api_key(
+    request: &mut reqwest::Request,
+    location: ApiKeyLocation,
+    name: &str,
+    key: &str,
+) -> ApiResult<()> {
+    match location {
+        ApiKeyLocation::Header => {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(ApiForgeError::auth)?;
+            request.headers_mut().insert(name, sensitive_value(key)?);
+        }
+        ApiKeyLocation::Query => {
+            request.url_mut().query_pairs_mut().append_pair(name, key);
+        }
+        ApiKeyLocation::Cookie => {
+            let cookie = format!("{}={}", name, key);
+            let cookie = match request.headers().get(COOKIE).and_then(|value| value.to_str().ok()) {
+                Some(existing) if !existing.is_empty() => format!("{}; {}", existing, cookie),
+                _ => cookie,
+            };
+            request.headers_mut().insert(COOKIE, sensitive_value(&cookie)?);
+        }
+    }
+    Ok(())
+}
+
+/// Sends an API key as a header, query parameter or cookie.
+///
+/// Requests with `AuthenticationMethod::ApiKey` declare where the key is sent. For other requests,
+/// the key is sent where the provider was created for, e.g. with [`header`](Self::header).
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{ApiClient, ApiKeyAuth, Request};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Debug, Request)]
+/// #[request(endpoint = "/forecast", response_type = "Forecast", authentication = ApiKey(query = "appid"))]
+/// struct GetForecast {
+///     city: String,
+/// }
+///
+/// #[derive(Deserialize, Debug)]
+/// struct Forecast {
+///     summary: String,
+/// }
+///
+/// let client = ApiClient::builder("https://api.example.com")
+///     .auth(ApiKeyAuth::new("my-key"))
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct ApiKeyAuth {
+    placement: Option<(ApiKeyLocation, String)>,
+    key: String,
+}
+
+impl ApiKeyAuth {
+    /// Creates a provider sending the key where the request's `AuthenticationMethod::ApiKey`
+    /// declares.
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            placement: None,
+            key: key.into(),
+        }
+    }
+
+    /// Creates a provider sending the key at the location under the name, unless the request
+    /// declares otherwise.
+    pub fn at(location: ApiKeyLocation, name: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            placement: Some((location, name.into())),
+            key: key.into(),
+        }
+    }
+
+    /// Sends the key in the header, e.g. `X-Api-Key`.
+    pub fn header(name: impl Into<String>, key: impl Into<String>) -> Self {
+        Self::at(ApiKeyLocation::Header, name, key)
+    }
+
+    /// Sends the key as the query parameter, e.g. `api_key`.
+    pub fn query(name: impl Into<String>, key: impl Into<String>) -> Self {
+        Self::at(ApiKeyLocation::Query, name, key)
+    }
+
+    /// Sends the key as the cookie.
+    pub fn cookie(name: impl Into<String>, key: impl Into<String>) -> Self {
+        Self::at(ApiKeyLocation::Cookie, name, key)
+    }
+}
+
+impl fmt::Debug for ApiKeyAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKeyAuth")
+            .field("placement", &self.placement)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl AuthProvider for ApiKeyAuth {
+    async fn apply(&self, request: &mut reqwest::Request, method: AuthenticationMethod) -> ApiResult<()> {
+        match (method, &self.placement) {
+            (AuthenticationMethod::ApiKey { location, name }, _) => apply_api_key(request, location, name, &self.key),
+            (_, Some((location, name))) => apply_api_key(request, *location, name, &self.key),
+            (method, None) => Err(ApiForgeError::auth(format!(
+                "The request uses {:?} authentication, so it doesn't declare where to send the API key",
+                method
+            ))),
+        }
+    }
+}
+
+/// Sends a fixed set of headers, for schemes such as `X-Client-Id` and `X-Client-Secret` pairs.
+///
+/// The values are marked as sensitive, so they are hidden from `Debug` output.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderAuth {
+    headers: HeaderMap,
+}
+
+impl HeaderAuth {
+    /// Creates a provider without headers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a header to send.
+    pub fn with_header(mut self, name: HeaderName, mut value: HeaderValue) -> Self {
+        value.set_sensitive(true);
+        self.headers.insert(name, value);
+        self
+    }
+}
+
+impl From<HeaderMap> for HeaderAuth {
+    fn from(mut headers: HeaderMap) -> Self {
+        for value in headers.values_mut() {
+            value.set_sensitive(true);
+        }
+        Self { headers }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl AuthProvider for HeaderAuth {
+    async fn apply(&self, request: &mut reqwest::Request, _method: AuthenticationMethod) -> ApiResult<()> {
+        let headers = request.headers_mut();
+        for name in self.headers.keys() {
+            headers.remove(name);
+        }
+        for (name, value) in &self.headers {
+            headers.append(name.clone(), value.clone());
+        }
+        Ok(())
+    }
+}
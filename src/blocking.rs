@@ -0,0 +1,543 @@
+use crate::auth::{authenticate, renew_credentials, AuthProvider, BasicAuth, BearerAuth};
+use crate::digest::DigestAuth;
+use crate::client::shared_http_client;
+use crate::decoder::{DecoderRegistry, ResponseDecoder};
+use crate::error::ErrorType;
+use crate::response::{decode_response, ApiResponse};
+use crate::retry::RetryPolicy;
+use crate::signing::{sign, RequestSigner};
+use crate::runtime::Instant;
+use crate::traits::ApiRequest;
+use crate::transport::{TransportBody, TransportRequest};
+use crate::{ApiForgeError, ApiResult};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use tracing::{debug, error, info, warn};
+
+/// Returns the process-wide `reqwest::blocking::Client` used by the blocking `ApiRequest` methods.
+pub(crate) fn shared_blocking_client() -> &'static reqwest::blocking::Client {
+    static CLIENT: OnceLock<reqwest::blocking::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::blocking::Client::new)
+}
+
+/// A blocking counterpart of [`ApiClient`](crate::ApiClient) for programs without an async runtime.
+///
+/// Requests are generated by the same `generate_request` implementation as the async client and
+/// responses are parsed with the same logic as `ApiRequest::from_response`. Streaming request
+/// bodies, such as multipart forms and `StreamBody`, are read into memory before they are sent.
+///
+/// Like `reqwest::blocking::Client`, this client must not be used from within an async runtime.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use api_forge::{BlockingApiClient, Request};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Debug, Request)]
+/// #[request(endpoint = "/posts", response_type = "Vec<Post>")]
+/// struct GetPosts;
+///
+/// #[derive(Deserialize, Debug)]
+/// struct Post {
+///     id: i32,
+///     title: String,
+/// }
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = BlockingApiClient::new("https://jsonplaceholder.typicode.com");
+///     let posts: Vec<Post> = client.execute(&GetPosts)?;
+///     println!("Fetched {} posts", posts.len());
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct BlockingApiClient {
+    http: reqwest::blocking::Client,
+    base_url: String,
+    default_headers: HeaderMap,
+    auth: Option<Arc<dyn AuthProvider>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    retry_policy: Option<RetryPolicy>,
+    decoders: Arc<DecoderRegistry>,
+}
+
+impl fmt::Debug for BlockingApiClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingApiClient")
+            .field("http", &self.http)
+            .field("base_url", &self.base_url)
+            .field("default_headers", &self.default_headers)
+            .field("auth", &self.auth.is_some())
+            .field("signer", &self.signer.is_some())
+            .field("retry_policy", &self.retry_policy)
+            .field("decoders", &self.decoders)
+            .finish()
+    }
+}
+
+impl BlockingApiClient {
+    /// Creates a new client for the given base URL with no default headers or credentials.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::builder(base_url).build()
+    }
+
+    /// Returns a builder for configuring a new client.
+    pub fn builder(base_url: impl Into<String>) -> BlockingApiClientBuilder {
+        BlockingApiClientBuilder::new(base_url)
+    }
+
+    /// Returns the base URL all requests are sent to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the headers added to every request.
+    pub fn default_headers(&self) -> &HeaderMap {
+        &self.default_headers
+    }
+
+    /// Returns the provider applying credentials to requests.
+    pub fn auth(&self) -> Option<&dyn AuthProvider> {
+        self.auth.as_deref()
+    }
+
+    /// Returns the signer applied to requests.
+    pub fn signer(&self) -> Option<&dyn RequestSigner> {
+        self.signer.as_deref()
+    }
+
+    /// Returns the retry policy used for requests that don't set their own.
+    pub fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry_policy.as_ref()
+    }
+
+    /// Returns the underlying `reqwest::blocking::Client`.
+    pub fn http_client(&self) -> &reqwest::blocking::Client {
+        &self.http
+    }
+
+    /// Returns the decoders used to parse response bodies.
+    pub fn decoders(&self) -> &DecoderRegistry {
+        &self.decoders
+    }
+
+    /// Sends the request and returns the raw response.
+    ///
+    /// The request is retried according to its own `RETRY_POLICY`, falling back to the client's
+    /// retry policy.
+    pub fn send<Res>(&self, request: &impl ApiRequest<Res>) -> ApiResult<reqwest::blocking::Response>
+    where
+        Res: DeserializeOwned,
+    {
+        self.send_authenticated(request, self.auth())
+    }
+
+    /// Sends the request with the given provider instead of the client's, and returns the raw
+    /// response.
+    pub fn send_with_auth<Res>(
+        &self,
+        request: &impl ApiRequest<Res>,
+        auth: &dyn AuthProvider,
+    ) -> ApiResult<reqwest::blocking::Response>
+    where
+        Res: DeserializeOwned,
+    {
+        self.send_authenticated(request, Some(auth))
+    }
+
+    /// Sends the request with the provider applying its credentials.
+    fn send_authenticated<Res>(
+        &self,
+        request: &impl ApiRequest<Res>,
+        auth: Option<&dyn AuthProvider>,
+    ) -> ApiResult<reqwest::blocking::Response>
+    where
+        Res: DeserializeOwned,
+    {
+        send_blocking(
+            request,
+            &self.http,
+            &self.base_url,
+            Some(self.default_headers.clone()),
+            auth,
+            self.signer.as_deref(),
+            self.retry_policy,
+        )
+    }
+
+    /// Sends the request and parses the response into the expected type.
+    pub fn execute<Res>(&self, request: &impl ApiRequest<Res>) -> ApiResult<Res>
+    where
+        Res: DeserializeOwned,
+    {
+        let response = self.send(request)?;
+        debug!("Response status: {}", response.status());
+        from_blocking_response(response, &self.decoders, error_type(request))
+    }
+
+    /// Sends the request with the given provider instead of the client's, and parses the
+    /// response into the expected type.
+    pub fn execute_with_auth<Res>(&self, request: &impl ApiRequest<Res>, auth: &dyn AuthProvider) -> ApiResult<Res>
+    where
+        Res: DeserializeOwned,
+    {
+        let response = self.send_with_auth(request, auth)?;
+        debug!("Response status: {}", response.status());
+        from_blocking_response(response, &self.decoders, error_type(request))
+    }
+
+    /// Sends the request and parses the response, returning the body together with the status,
+    /// headers, final URL and elapsed time.
+    pub fn execute_with_meta<Res>(&self, request: &impl ApiRequest<Res>) -> ApiResult<ApiResponse<Res>>
+    where
+        Res: DeserializeOwned,
+    {
+        let start = Instant::now();
+        let response = self.send(request)?;
+        debug!("Response status: {}", response.status());
+        from_blocking_response_with_meta(response, start, &self.decoders, error_type(request))
+    }
+}
+
+/// Builder for [`BlockingApiClient`].
+pub struct BlockingApiClientBuilder {
+    base_url: String,
+    http: Option<reqwest::blocking::Client>,
+    default_headers: HeaderMap,
+    auth: Option<Arc<dyn AuthProvider>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    retry_policy: Option<RetryPolicy>,
+    decoders: DecoderRegistry,
+    strict_empty_body: Option<bool>,
+    error_type: Option<ErrorType>,
+}
+
+impl fmt::Debug for BlockingApiClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingApiClientBuilder")
+            .field("base_url", &self.base_url)
+            .field("http", &self.http)
+            .field("default_headers", &self.default_headers)
+            .field("auth", &self.auth.is_some())
+            .field("signer", &self.signer.is_some())
+            .field("retry_policy", &self.retry_policy)
+            .field("decoders", &self.decoders)
+            .field("strict_empty_body", &self.strict_empty_body)
+            .field("error_type", &self.error_type.is_some())
+            .finish()
+    }
+}
+
+impl BlockingApiClientBuilder {
+    /// Creates a new builder for the given base URL.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            http: None,
+            default_headers: HeaderMap::new(),
+            auth: None,
+            signer: None,
+            retry_policy: None,
+            decoders: DecoderRegistry::default(),
+            strict_empty_body: None,
+            error_type: None,
+        }
+    }
+
+    /// Uses an existing `reqwest::blocking::Client` instead of creating a new one.
+    pub fn http_client(mut self, client: reqwest::blocking::Client) -> Self {
+        self.http = Some(client);
+        self
+    }
+
+    /// Adds a header that is sent with every request.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// Adds several headers that are sent with every request.
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers.extend(headers);
+        self
+    }
+
+    /// Sets the provider applying credentials to requests that use authentication.
+    ///
+    /// The provider's future is driven to completion on the calling thread.
+    pub fn auth(mut self, provider: impl AuthProvider) -> Self {
+        self.auth = Some(Arc::new(provider));
+        self
+    }
+
+    /// Sets the signer applied to every request, see `RequestSigner`.
+    pub fn signer(mut self, signer: impl RequestSigner) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Sends the token with `BearerAuth`.
+    pub fn bearer_auth(self, token: impl Into<String>) -> Self {
+        self.auth(BearerAuth::new(token))
+    }
+
+    /// Sends the username and password with `BasicAuth`.
+    pub fn basic_auth(self, username: impl Into<String>, password: Option<String>) -> Self {
+        self.auth(BasicAuth::new(username, password))
+    }
+
+    /// Sends the username and password with `DigestAuth`.
+    pub fn digest_auth(self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.auth(DigestAuth::new(username, password))
+    }
+
+    /// Sets the retry policy used for requests that don't set their own.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Registers a decoder for a media type, suffix such as `+json` or top-level type such as
+    /// `text/*`, replacing the default decoder for it.
+    pub fn decoder(mut self, media_type: &str, decoder: impl ResponseDecoder + 'static) -> Self {
+        self.decoders.register(media_type, decoder);
+        self
+    }
+
+    /// Makes empty bodies of successful responses, other than `204 No Content`, fail with
+    /// `ApiForgeError::EmptyBody` even if the response type accepts them.
+    pub fn strict_empty_body(mut self, strict: bool) -> Self {
+        self.strict_empty_body = Some(strict);
+        self
+    }
+
+    /// Deserializes error bodies into `E` for requests that don't set their own error type, see
+    /// [`ErrorType`](crate::ErrorType).
+    pub fn error_type<E>(mut self) -> Self
+    where
+        E: DeserializeOwned + std::fmt::Debug + Send + Sync + 'static,
+    {
+        self.error_type = Some(ErrorType::of::<E>());
+        self
+    }
+
+    /// Replaces all decoders, including the defaults, with the registry.
+    ///
+    /// `strict_empty_body` and `error_type` still apply, whether they are set before or after.
+    pub fn decoders(mut self, decoders: DecoderRegistry) -> Self {
+        self.decoders = decoders;
+        self
+    }
+
+    /// Builds the client.
+    pub fn build(mut self) -> BlockingApiClient {
+        if let Some(strict) = self.strict_empty_body {
+            self.decoders.set_strict(strict);
+        }
+        if let Some(error_type) = self.error_type {
+            self.decoders.set_error_type(error_type);
+        }
+
+        BlockingApiClient {
+            http: self.http.unwrap_or_else(|| shared_blocking_client().clone()),
+            base_url: self.base_url,
+            default_headers: self.default_headers,
+            auth: self.auth,
+            signer: self.signer,
+            retry_policy: self.retry_policy,
+            decoders: Arc::new(self.decoders),
+        }
+    }
+}
+
+/// Parses a blocking response and wraps it with the response metadata.
+///
+/// `start` is when the request was sent, for the elapsed time.
+pub(crate) fn from_blocking_response_with_meta<Res>(
+    resp: reqwest::blocking::Response,
+    start: Instant,
+    decoders: &DecoderRegistry,
+    error_type: Option<ErrorType>,
+) -> ApiResult<ApiResponse<Res>>
+where
+    Res: DeserializeOwned,
+{
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    let url = resp.url().clone();
+    let body = from_blocking_response(resp, decoders, error_type)?;
+    Ok(ApiResponse {
+        body,
+        status,
+        headers,
+        url,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Returns the error type declared by the request's type.
+fn error_type<Req, Res>(_request: &Req) -> Option<ErrorType>
+where
+    Req: ApiRequest<Res>,
+    Res: DeserializeOwned,
+{
+    Req::ERROR_TYPE
+}
+
+/// Generates the request with `generate_request` and sends it with the blocking client.
+///
+/// `fallback_policy` is used if the request doesn't declare its own `RETRY_POLICY`.
+pub(crate) fn send_blocking<Req, Res>(
+    request: &Req,
+    client: &reqwest::blocking::Client,
+    base_url: &str,
+    headers: Option<HeaderMap>,
+    auth: Option<&dyn AuthProvider>,
+    signer: Option<&dyn RequestSigner>,
+    fallback_policy: Option<RetryPolicy>,
+) -> ApiResult<reqwest::blocking::Response>
+where
+    Req: ApiRequest<Res> + ?Sized,
+    Res: DeserializeOwned,
+{
+    info!("Sending blocking request to {}{}...", base_url, Req::ENDPOINT);
+    debug!("Request: {:?}", request);
+    // Bodies that can only be sent once are never retried or resent with new credentials
+    let replayable = request.is_replayable();
+    let policy = Req::RETRY_POLICY.or(fallback_policy).filter(|_| replayable);
+
+    let send = || {
+        let mut generated = request
+            .generate_request(shared_http_client(), base_url, headers.clone())?
+            .build()?;
+        block_on(authenticate(&mut generated, auth, Req::AUTHENTICATION_METHOD, None))??;
+        block_on(sign(&mut generated, signer))??;
+        let sent = generated.headers().clone();
+        ApiResult::<_>::Ok((sent, client.execute(block_on(into_blocking_request(generated))??)?))
+    };
+
+    send_with_retry_blocking(policy.as_ref(), &Req::METHOD, || {
+        let (sent, response) = send()?;
+        let status = response.status();
+        let renewed = block_on(renew_credentials(
+            auth,
+            Req::AUTHENTICATION_METHOD,
+            &sent,
+            status,
+            response.headers(),
+        ))??;
+        if renewed && replayable {
+            return Ok(send()?.1);
+        }
+        Ok(response)
+    })
+}
+
+/// Drives a future, such as an `AuthProvider` call, to completion on the calling thread.
+///
+/// The future runs on a runtime shared by all blocking clients, so providers can do I/O, and the
+/// connections of providers fetching tokens are kept alive between calls.
+fn block_on<F>(future: F) -> ApiResult<F::Output>
+where
+    F: Future,
+{
+    static RUNTIME: OnceLock<Result<tokio::runtime::Runtime, String>> = OnceLock::new();
+
+    let runtime = RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("api-forge-blocking")
+            .enable_all()
+            .build()
+            .map_err(|err| err.to_string())
+    });
+    match runtime {
+        Ok(runtime) => Ok(runtime.block_on(future)),
+        Err(err) => Err(ApiForgeError::transport(format!("Failed to start the blocking runtime: {}", err))),
+    }
+}
+
+/// Converts a request built for the async client into a blocking request.
+///
+/// Streaming bodies, such as multipart forms, are read into memory, as the blocking client can't
+/// poll them.
+async fn into_blocking_request(request: reqwest::Request) -> ApiResult<reqwest::blocking::Request> {
+    let request = TransportRequest::from(request);
+    let mut converted = reqwest::blocking::Request::new(request.method, request.url);
+    *converted.headers_mut() = request.headers;
+    *converted.timeout_mut() = request.timeout;
+
+    match request.body {
+        TransportBody::Empty => {}
+        TransportBody::Bytes(bytes) => *converted.body_mut() = Some(bytes.into()),
+        body @ TransportBody::Stream(_) => {
+            debug!("Reading streaming request body into memory");
+            *converted.body_mut() = Some(body.collect().await?.into());
+        }
+    }
+
+    Ok(converted)
+}
+
+/// Parses a blocking response with the same logic as `ApiRequest::from_response_with`.
+pub(crate) fn from_blocking_response<Res>(
+    resp: reqwest::blocking::Response,
+    decoders: &DecoderRegistry,
+    error_type: Option<ErrorType>,
+) -> ApiResult<Res>
+where
+    Res: DeserializeOwned,
+{
+    debug!("Received response: {:?}", resp);
+    let status = resp.status();
+    let headers = resp.headers().clone();
+
+    let body = match resp.bytes() {
+        Ok(body) => body,
+        // The status is reported for failed responses even if the body can't be read
+        Err(_) if !status.is_success() => Default::default(),
+        Err(e) => {
+            error!("Failed to read response body: {}", e);
+            return Err(ApiForgeError::ParseError(e));
+        }
+    };
+
+    decode_response(status, &headers, &body, decoders, error_type)
+}
+
+/// Blocking version of the retry loop used by the async clients.
+fn send_with_retry_blocking<F>(
+    policy: Option<&RetryPolicy>,
+    method: &Method,
+    mut send: F,
+) -> ApiResult<reqwest::blocking::Response>
+where
+    F: FnMut() -> ApiResult<reqwest::blocking::Response>,
+{
+    let policy = match policy {
+        Some(policy) if policy.is_retryable_method(method) => policy,
+        _ => return send(),
+    };
+
+    let mut attempt = 1;
+    loop {
+        let result = send();
+
+        let outcome = result.as_ref().map(|response| (response.status(), response.headers()));
+        let Some(delay) = policy.retry_delay(attempt, outcome) else {
+            return result;
+        };
+
+        warn!(
+            "Attempt {}/{} failed, retrying in {:?}",
+            attempt, policy.max_attempts, delay
+        );
+        std::thread::sleep(delay);
+        attempt += 1;
+    }
+}
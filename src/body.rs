@@ -0,0 +1,203 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::BoxError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::{BodyStream, TransportBody};
+use bytes::Bytes;
+#[cfg(not(target_arch = "wasm32"))]
+use futures_util::{stream, Stream, TryStreamExt};
+#[cfg(not(target_arch = "wasm32"))]
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Mutex;
+
+/// A request body that is streamed instead of being held in memory.
+///
+/// Used as the `#[request(body)]` field of requests with `transmission = Stream`, or as a
+/// `#[request(file)]` part of `Multipart` requests. If the length is known, it is sent as
+/// `Content-Length`, otherwise the body is sent with chunked encoding.
+///
+/// The stream can only be sent once, so requests with a stream body are not replayable: they are
+/// never retried or resent with renewed credentials, and the first response is returned.
+///
+/// Request structs derive `Serialize`, so fields of this type need `#[serde(skip)]`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use api_forge::{ApiClient, Request, StreamBody};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, Debug, Request)]
+/// #[request(endpoint = "/artifacts", method = PUT, transmission = Stream)]
+/// struct UploadArtifact {
+///     // Sent as a query parameter
+///     name: String,
+///     #[serde(skip)]
+///     #[request(body)]
+///     artifact: StreamBody,
+/// }
+///
+/// # #[cfg(feature = "native")]
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let request = UploadArtifact {
+///     name: "release.tar.gz".to_string(),
+///     artifact: StreamBody::from_file("release.tar.gz").await?,
+/// };
+/// ApiClient::new("https://artifacts.example.com").execute(&request).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub struct StreamBody {
+    stream: Mutex<Option<BodyStream>>,
+    length: Option<u64>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StreamBody {
+    /// Creates a body from a boxed stream of chunks.
+    pub(crate) fn new(stream: BodyStream) -> Self {
+        Self {
+            stream: Mutex::new(Some(stream)),
+            length: None,
+        }
+    }
+
+    /// Creates a body from a stream of chunks.
+    pub fn from_stream<S, B, E>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<B, E>> + Send + 'static,
+        B: Into<Bytes> + 'static,
+        E: Into<BoxError> + 'static,
+    {
+        Self::new(Box::pin(stream.map_ok(Into::into).map_err(Into::into)))
+    }
+
+    /// Opens the file and creates a body streaming it, with the file size as length.
+    #[cfg(feature = "native")]
+    pub async fn from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let file = tokio::fs::File::open(path).await?;
+        let length = file.metadata().await?.len();
+        Ok(Self::from_reader(file).with_length(length))
+    }
+
+    /// Creates a body reading from an `AsyncRead`.
+    #[cfg(feature = "native")]
+    pub fn from_reader<R>(reader: R) -> Self
+    where
+        R: tokio::io::AsyncRead + Send + 'static,
+    {
+        use futures_util::TryStreamExt;
+
+        Self::new(Box::pin(
+            tokio_util::io::ReaderStream::new(reader).map_err(BoxError::from),
+        ))
+    }
+
+    /// Sets the exact length of the body in bytes.
+    ///
+    /// The stream must produce exactly this many bytes, otherwise the request fails.
+    pub fn with_length(mut self, length: u64) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// Returns the length of the body, if known.
+    pub fn length(&self) -> Option<u64> {
+        self.length
+    }
+
+    /// Takes the stream as a `reqwest::Body`, reporting the length if it is known.
+    pub fn to_body(&self) -> reqwest::Body {
+        TransportBody::Stream(self.take()).into_reqwest_body(self.length)
+    }
+
+    /// Takes the stream, leaving a stream that fails if the body is sent again.
+    pub(crate) fn take(&self) -> BodyStream {
+        self.stream
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take()
+            .unwrap_or_else(|| {
+                Box::pin(stream::once(async {
+                    Err::<Bytes, _>(BoxError::from("Stream body was already sent"))
+                }))
+            })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl fmt::Debug for StreamBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamBody")
+            .field("length", &self.length)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A field sent verbatim as the whole body of a request with `transmission = Raw`.
+///
+/// The content type is sent unless the request sets its own with `content_type`.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::Request;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, Debug, Request)]
+/// #[request(endpoint = "/imports", method = POST, transmission = Raw, content_type = "text/csv")]
+/// struct ImportCsv {
+///     // Sent as a query parameter
+///     dry_run: bool,
+///     #[request(body)]
+///     csv: String,
+/// }
+/// ```
+pub trait RawBody {
+    /// The content type of the body.
+    fn content_type(&self) -> &'static str;
+
+    /// Creates the request body.
+    fn to_body(&self) -> reqwest::Body;
+}
+
+impl RawBody for Vec<u8> {
+    fn content_type(&self) -> &'static str {
+        "application/octet-stream"
+    }
+
+    fn to_body(&self) -> reqwest::Body {
+        self.clone().into()
+    }
+}
+
+impl RawBody for Bytes {
+    fn content_type(&self) -> &'static str {
+        "application/octet-stream"
+    }
+
+    fn to_body(&self) -> reqwest::Body {
+        self.clone().into()
+    }
+}
+
+impl RawBody for String {
+    fn content_type(&self) -> &'static str {
+        "text/plain; charset=utf-8"
+    }
+
+    fn to_body(&self) -> reqwest::Body {
+        self.clone().into()
+    }
+}
+
+impl RawBody for &'static str {
+    fn content_type(&self) -> &'static str {
+        "text/plain; charset=utf-8"
+    }
+
+    fn to_body(&self) -> reqwest::Body {
+        (*self).into()
+    }
+}
@@ -0,0 +1,36 @@
+use crate::decoder::{DecodeSeed, MediaType, ResponseDecoder};
+use crate::error::ApiForgeError;
+use crate::ApiResult;
+use serde::Serialize;
+use tracing::{debug, error};
+
+/// Content type of CBOR bodies.
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+/// Creates the request body for `DataTransmissionMethod::Cbor`.
+pub fn cbor_body<T: Serialize + ?Sized>(value: &T) -> ApiResult<reqwest::Body> {
+    let mut encoded = Vec::new();
+    ciborium::into_writer(value, &mut encoded).map_err(ApiForgeError::serialize)?;
+    Ok(encoded.into())
+}
+
+/// Decodes CBOR bodies with `ciborium`.
+///
+/// The body is decoded into a `serde_value::Value` first, as `ciborium` only deserializes into
+/// concrete types. Tags are skipped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CborDecoder;
+
+impl ResponseDecoder for CborDecoder {
+    fn decode(&self, body: &[u8], _media_type: &MediaType, seed: DecodeSeed<'_>) -> ApiResult<()> {
+        debug!("Parsing response as CBOR");
+        let value = ciborium::from_reader::<serde_value::Value, _>(body).map_err(|e| {
+            error!("CBOR parsing error: {}", e);
+            ApiForgeError::decode(e)
+        })?;
+        seed.deserialize(value).map_err(|e| {
+            error!("CBOR parsing error: {}", e);
+            ApiForgeError::decode(e)
+        })
+    }
+}
@@ -0,0 +1,505 @@
+use crate::auth::{authenticate, renew_credentials, AuthProvider, BasicAuth, BearerAuth};
+use crate::digest::DigestAuth;
+use crate::decoder::{DecoderRegistry, ResponseDecoder};
+use crate::error::ErrorType;
+use crate::middleware::{Middleware, Next};
+use crate::rate_limit::RateLimiter;
+use crate::response::ApiResponse;
+use crate::retry::{send_with_retry, RetryPolicy};
+use crate::signing::{sign, RequestSigner};
+use crate::runtime::Instant;
+use crate::traits::{ApiRequest, AuthenticationMethod};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::{ReqwestTransport, Transport};
+use crate::ApiResult;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use tracing::{debug, info};
+
+/// Returns the process-wide `reqwest::Client` used by the `ApiRequest` convenience methods.
+///
+/// The client is created on first use and shared afterwards, so connection pools and TLS
+/// sessions are reused across requests.
+pub(crate) fn shared_http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+/// A reusable client for sending `ApiRequest`s to a single API.
+///
+/// `ApiClient` owns one `reqwest::Client` together with the base URL, default headers,
+/// credentials, retry policy, rate limiter and middleware of the API it talks to. Cloning it is
+/// cheap and clones share the same connection pool and rate limits.
+///
+/// Requests are built with the `reqwest::Client` and sent through a `Transport`, which
+/// defaults to `ReqwestTransport` using the same client. On `wasm32`, requests are always sent
+/// with the fetch-based `reqwest::Client`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use api_forge::{ApiClient, Request};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Debug, Request)]
+/// #[request(endpoint = "/posts", response_type = "Vec<Post>")]
+/// struct GetPosts;
+///
+/// #[derive(Deserialize, Debug)]
+/// struct Post {
+///     id: i32,
+///     title: String,
+/// }
+///
+/// async fn example() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = ApiClient::builder("https://jsonplaceholder.typicode.com")
+///         .bearer_auth("my-token")
+///         .build();
+///
+///     let posts: Vec<Post> = client.execute(&GetPosts).await?;
+///     println!("Fetched {} posts", posts.len());
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: String,
+    default_headers: HeaderMap,
+    auth: Option<Arc<dyn AuthProvider>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    decoders: Arc<DecoderRegistry>,
+    #[cfg(not(target_arch = "wasm32"))]
+    transport: Arc<dyn Transport>,
+}
+
+impl fmt::Debug for ApiClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiClient")
+            .field("base_url", &self.base_url)
+            .field("default_headers", &self.default_headers)
+            .field("auth", &self.auth.is_some())
+            .field("signer", &self.signer.is_some())
+            .field("retry_policy", &self.retry_policy)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("middleware", &self.middleware.len())
+            .field("decoders", &self.decoders)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ApiClient {
+    /// Creates a new client for the given base URL with no default headers or credentials.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::builder(base_url).build()
+    }
+
+    /// Returns a builder for configuring a new client.
+    pub fn builder(base_url: impl Into<String>) -> ApiClientBuilder {
+        ApiClientBuilder::new(base_url)
+    }
+
+    /// Returns the base URL all requests are sent to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the headers added to every request.
+    pub fn default_headers(&self) -> &HeaderMap {
+        &self.default_headers
+    }
+
+    /// Returns the provider applying credentials to requests.
+    pub fn auth(&self) -> Option<&dyn AuthProvider> {
+        self.auth.as_deref()
+    }
+
+    /// Returns the signer applied to requests.
+    pub fn signer(&self) -> Option<&dyn RequestSigner> {
+        self.signer.as_deref()
+    }
+
+    /// Returns the retry policy used for requests that don't set their own.
+    pub fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry_policy.as_ref()
+    }
+
+    /// Returns the rate limiter applied to every request.
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_deref()
+    }
+
+    /// Returns the underlying `reqwest::Client`.
+    pub fn http_client(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Returns the decoders used to parse response bodies.
+    pub fn decoders(&self) -> &DecoderRegistry {
+        &self.decoders
+    }
+
+    /// Returns the transport requests are sent with.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn transport(&self) -> &dyn Transport {
+        self.transport.as_ref()
+    }
+
+    /// Builds the `reqwest::RequestBuilder` for a request using this client's configuration.
+    ///
+    /// Credentials are not included, as they are applied by the `AuthProvider` when sending.
+    /// Fails if the body can't be encoded.
+    pub fn request<Res>(&self, request: &impl ApiRequest<Res>) -> ApiResult<reqwest::RequestBuilder>
+    where
+        Res: DeserializeOwned,
+    {
+        request.generate_request(&self.http, &self.base_url, Some(self.default_headers.clone()))
+    }
+
+    /// Sends the request and returns the raw response.
+    ///
+    /// The request is retried according to its own `RETRY_POLICY`, falling back to the client's
+    /// retry policy, unless it isn't replayable, see `ApiRequest::is_replayable`. Every attempt
+    /// waits for the client's rate limiter first and then runs through the client's middleware.
+    pub async fn send<Res>(&self, request: &impl ApiRequest<Res>) -> ApiResult<reqwest::Response>
+    where
+        Res: DeserializeOwned,
+    {
+        self.send_authenticated(request, self.auth()).await
+    }
+
+    /// Sends the request with the given provider instead of the client's, and returns the raw
+    /// response.
+    ///
+    /// Use it for requests made on behalf of another user, or with credentials other than the
+    /// client's. Apart from the credentials, the request is sent like with `send`.
+    pub async fn send_with_auth<Res>(
+        &self,
+        request: &impl ApiRequest<Res>,
+        auth: &dyn AuthProvider,
+    ) -> ApiResult<reqwest::Response>
+    where
+        Res: DeserializeOwned,
+    {
+        self.send_authenticated(request, Some(auth)).await
+    }
+
+    /// Sends the request with the provider applying its credentials.
+    async fn send_authenticated<Res>(
+        &self,
+        request: &impl ApiRequest<Res>,
+        auth: Option<&dyn AuthProvider>,
+    ) -> ApiResult<reqwest::Response>
+    where
+        Res: DeserializeOwned,
+    {
+        info!("Sending request to {}...", self.base_url);
+        debug!("Request: {:?}", request);
+        let RequestSettings {
+            endpoint,
+            method,
+            authentication,
+            retry_policy,
+        } = request_settings(request);
+        // Bodies that can only be sent once are never retried or resent with new credentials
+        let replayable = request.is_replayable();
+        let policy = retry_policy.or(self.retry_policy).filter(|_| replayable);
+
+        send_with_retry(policy.as_ref(), &method, || async move {
+            if let Some(limiter) = self.rate_limiter.as_deref() {
+                limiter.acquire(endpoint).await;
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            let next = Next::new(self.transport(), &self.middleware);
+            #[cfg(target_arch = "wasm32")]
+            let next = Next::new(&self.http, &self.middleware);
+
+            let http_request = self.prepare(request, auth, authentication, next).await?;
+            let sent = http_request.headers().clone();
+            let mut response = next.run(http_request).await?;
+            let renewed = renew_credentials(auth, authentication, &sent, response.status(), response.headers()).await?;
+            if renewed && replayable {
+                response = next.run(self.prepare(request, auth, authentication, next).await?).await?;
+            }
+
+            if let Some(limiter) = self.rate_limiter.as_deref() {
+                limiter.update(endpoint, response.status(), response.headers());
+            }
+            Ok(response)
+        })
+        .await
+    }
+
+    /// Builds the request, then applies the credentials and signature.
+    async fn prepare<Res>(
+        &self,
+        request: &impl ApiRequest<Res>,
+        auth: Option<&dyn AuthProvider>,
+        authentication: AuthenticationMethod,
+        next: Next<'_>,
+    ) -> ApiResult<reqwest::Request>
+    where
+        Res: DeserializeOwned,
+    {
+        let mut http_request = self.request(request)?.build()?;
+        authenticate(&mut http_request, auth, authentication, Some(next)).await?;
+        sign(&mut http_request, self.signer()).await?;
+        Ok(http_request)
+    }
+
+    /// Sends the request and parses the response into the expected type.
+    pub async fn execute<Res>(&self, request: &impl ApiRequest<Res>) -> ApiResult<Res>
+    where
+        Res: DeserializeOwned,
+    {
+        let response = self.send(request).await?;
+        debug!("Response status: {}", response.status());
+        parse_response(request, response, &self.decoders).await
+    }
+
+    /// Sends the request with the given provider instead of the client's, and parses the
+    /// response into the expected type.
+    pub async fn execute_with_auth<Res>(&self, request: &impl ApiRequest<Res>, auth: &dyn AuthProvider) -> ApiResult<Res>
+    where
+        Res: DeserializeOwned,
+    {
+        let response = self.send_with_auth(request, auth).await?;
+        debug!("Response status: {}", response.status());
+        parse_response(request, response, &self.decoders).await
+    }
+
+    /// Sends the request and parses the response, returning the body together with the status,
+    /// headers, final URL and elapsed time.
+    pub async fn execute_with_meta<Res>(&self, request: &impl ApiRequest<Res>) -> ApiResult<ApiResponse<Res>>
+    where
+        Res: DeserializeOwned,
+    {
+        let start = Instant::now();
+        let response = self.send(request).await?;
+        debug!("Response status: {}", response.status());
+        let status = response.status();
+        let headers = response.headers().clone();
+        let url = response.url().clone();
+        let body = parse_response(request, response, &self.decoders).await?;
+        Ok(ApiResponse {
+            body,
+            status,
+            headers,
+            url,
+            elapsed: start.elapsed(),
+        })
+    }
+}
+
+/// Parses a response with the `from_response_with` implementation of the request's type.
+async fn parse_response<Req, Res>(
+    _request: &Req,
+    response: reqwest::Response,
+    decoders: &DecoderRegistry,
+) -> ApiResult<Res>
+where
+    Req: ApiRequest<Res>,
+    Res: DeserializeOwned,
+{
+    Req::from_response_with(response, decoders).await
+}
+
+/// Settings declared by a request's type through the `ApiRequest` constants.
+struct RequestSettings {
+    endpoint: &'static str,
+    method: reqwest::Method,
+    authentication: AuthenticationMethod,
+    retry_policy: Option<RetryPolicy>,
+}
+
+/// Returns the settings declared by the request's type.
+fn request_settings<Req, Res>(_request: &Req) -> RequestSettings
+where
+    Req: ApiRequest<Res>,
+    Res: DeserializeOwned,
+{
+    RequestSettings {
+        endpoint: Req::ENDPOINT,
+        method: Req::METHOD,
+        authentication: Req::AUTHENTICATION_METHOD,
+        retry_policy: Req::RETRY_POLICY,
+    }
+}
+
+/// Builder for [`ApiClient`].
+pub struct ApiClientBuilder {
+    base_url: String,
+    http: Option<reqwest::Client>,
+    default_headers: HeaderMap,
+    auth: Option<Arc<dyn AuthProvider>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    decoders: DecoderRegistry,
+    strict_empty_body: Option<bool>,
+    error_type: Option<ErrorType>,
+    #[cfg(not(target_arch = "wasm32"))]
+    transport: Option<Arc<dyn Transport>>,
+}
+
+impl ApiClientBuilder {
+    /// Creates a new builder for the given base URL.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            http: None,
+            default_headers: HeaderMap::new(),
+            auth: None,
+            signer: None,
+            retry_policy: None,
+            rate_limiter: None,
+            middleware: Vec::new(),
+            decoders: DecoderRegistry::default(),
+            strict_empty_body: None,
+            error_type: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            transport: None,
+        }
+    }
+
+    /// Uses an existing `reqwest::Client` instead of creating a new one.
+    ///
+    /// Unless a custom transport is set, requests are also sent with this client.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http = Some(client);
+        self
+    }
+
+    /// Adds a header that is sent with every request.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// Adds several headers that are sent with every request.
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers.extend(headers);
+        self
+    }
+
+    /// Sets the provider applying credentials to requests that use authentication.
+    pub fn auth(mut self, provider: impl AuthProvider) -> Self {
+        self.auth = Some(Arc::new(provider));
+        self
+    }
+
+    /// Sets the signer applied to every request, see `RequestSigner`.
+    pub fn signer(mut self, signer: impl RequestSigner) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Sends the token with `BearerAuth`.
+    pub fn bearer_auth(self, token: impl Into<String>) -> Self {
+        self.auth(BearerAuth::new(token))
+    }
+
+    /// Sends the username and password with `BasicAuth`.
+    pub fn basic_auth(self, username: impl Into<String>, password: Option<String>) -> Self {
+        self.auth(BasicAuth::new(username, password))
+    }
+
+    /// Sends the username and password with `DigestAuth`.
+    pub fn digest_auth(self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.auth(DigestAuth::new(username, password))
+    }
+
+    /// Sets the retry policy used for requests that don't set their own.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Sets the rate limiter applied to every request.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(Arc::new(limiter));
+        self
+    }
+
+    /// Appends a middleware to the chain. Middleware runs in the order it was added.
+    pub fn middleware(mut self, middleware: impl Middleware) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Registers a decoder for a media type, suffix such as `+json` or top-level type such as
+    /// `text/*`, replacing the default decoder for it.
+    pub fn decoder(mut self, media_type: &str, decoder: impl ResponseDecoder + 'static) -> Self {
+        self.decoders.register(media_type, decoder);
+        self
+    }
+
+    /// Makes empty bodies of successful responses, other than `204 No Content`, fail with
+    /// `ApiForgeError::EmptyBody` even if the response type accepts them.
+    pub fn strict_empty_body(mut self, strict: bool) -> Self {
+        self.strict_empty_body = Some(strict);
+        self
+    }
+
+    /// Deserializes error bodies into `E` for requests that don't set their own error type, see
+    /// [`ErrorType`](crate::ErrorType).
+    pub fn error_type<E>(mut self) -> Self
+    where
+        E: DeserializeOwned + fmt::Debug + Send + Sync + 'static,
+    {
+        self.error_type = Some(ErrorType::of::<E>());
+        self
+    }
+
+    /// Replaces all decoders, including the defaults, with the registry.
+    ///
+    /// `strict_empty_body` and `error_type` still apply, whether they are set before or after.
+    pub fn decoders(mut self, decoders: DecoderRegistry) -> Self {
+        self.decoders = decoders;
+        self
+    }
+
+    /// Sends requests with a custom transport instead of reqwest.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn transport(mut self, transport: impl Transport) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Builds the client.
+    pub fn build(mut self) -> ApiClient {
+        if let Some(strict) = self.strict_empty_body {
+            self.decoders.set_strict(strict);
+        }
+        if let Some(error_type) = self.error_type {
+            self.decoders.set_error_type(error_type);
+        }
+
+        let http = self.http.unwrap_or_default();
+        #[cfg(not(target_arch = "wasm32"))]
+        let transport = self
+            .transport
+            .unwrap_or_else(|| Arc::new(ReqwestTransport::new(http.clone())));
+
+        ApiClient {
+            http,
+            base_url: self.base_url,
+            default_headers: self.default_headers,
+            auth: self.auth,
+            signer: self.signer,
+            retry_policy: self.retry_policy,
+            rate_limiter: self.rate_limiter,
+            middleware: self.middleware,
+            decoders: Arc::new(self.decoders),
+            #[cfg(not(target_arch = "wasm32"))]
+            transport,
+        }
+    }
+}
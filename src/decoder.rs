@@ -0,0 +1,543 @@
+use crate::error::{ApiForgeError, ErrorType};
+use crate::ApiResult;
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use tracing::{debug, error};
+
+/// A parsed `Content-Type` such as `application/vnd.api+json; charset=utf-8`.
+///
+/// The type, subtype and parameter names are compared case-insensitively and stored in lowercase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    essence: String,
+    parameters: Vec<(String, String)>,
+}
+
+impl MediaType {
+    /// Parses a media type, returning `None` if it isn't of the form `type/subtype`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut segments = split_unquoted(value, ';').into_iter();
+        let essence = segments.next()?.trim().to_lowercase();
+        let (type_name, subtype) = essence.split_once('/')?;
+        let is_token = |s: &str| !s.is_empty() && !s.contains(|c: char| c.is_whitespace() || c == '/');
+        if !is_token(type_name) || !is_token(subtype) {
+            return None;
+        }
+
+        let parameters = segments
+            .filter_map(|parameter| {
+                let (name, value) = parameter.split_once('=')?;
+                Some((name.trim().to_lowercase(), unquote(value.trim())))
+            })
+            .collect();
+
+        Some(Self { essence, parameters })
+    }
+
+    /// Returns `type/subtype` without parameters, e.g. `application/vnd.api+json`.
+    pub fn essence(&self) -> &str {
+        &self.essence
+    }
+
+    /// Returns the top-level type, e.g. `application`.
+    pub fn type_name(&self) -> &str {
+        self.essence.split_once('/').map_or("", |(type_name, _)| type_name)
+    }
+
+    /// Returns the subtype, e.g. `vnd.api+json`.
+    pub fn subtype(&self) -> &str {
+        self.essence.split_once('/').map_or("", |(_, subtype)| subtype)
+    }
+
+    /// Returns the structured syntax suffix of the subtype, e.g. `json` for `vnd.api+json`.
+    pub fn suffix(&self) -> Option<&str> {
+        self.subtype().rsplit_once('+').map(|(_, suffix)| suffix)
+    }
+
+    /// Returns the value of a parameter such as `charset`, without quotes.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| parameter.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the `charset` parameter, e.g. `utf-8`.
+    pub fn charset(&self) -> Option<&str> {
+        self.parameter("charset")
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.essence)?;
+        for (name, value) in &self.parameters {
+            write!(f, "; {}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns a text body as UTF-8, transcoding it from the `charset` of its media type.
+///
+/// Bodies without a charset or in UTF-8 are returned as is. Fails with `UnsupportedContentType`
+/// for unknown charsets and with `DecodeError` for bodies that aren't valid in their charset.
+/// Charset labels are resolved as browsers do, so e.g. `ISO-8859-1` is decoded as `windows-1252`.
+pub fn utf8_body<'a>(body: &'a [u8], media_type: &MediaType) -> ApiResult<Cow<'a, [u8]>> {
+    let Some(charset) = media_type.charset() else {
+        return Ok(Cow::Borrowed(body));
+    };
+    let Some(encoding) = encoding_rs::Encoding::for_label(charset.trim().as_bytes()) else {
+        error!("Unsupported charset {}", charset);
+        return Err(ApiForgeError::UnsupportedContentType(media_type.to_string()));
+    };
+    if encoding == encoding_rs::UTF_8 {
+        return Ok(Cow::Borrowed(body));
+    }
+
+    debug!("Transcoding response body from {} to UTF-8", encoding.name());
+    match encoding.decode_without_bom_handling_and_without_replacement(body) {
+        Some(text) => Ok(Cow::Owned(text.into_owned().into_bytes())),
+        None => Err(ApiForgeError::decode(format!("Response body is not valid {}", encoding.name()))),
+    }
+}
+
+/// Splits on a separator that isn't inside a quoted string.
+pub(crate) fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                segments.push(&value[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    segments.push(&value[start..]);
+    segments
+}
+
+/// Removes the quotes and escapes of a quoted parameter value.
+pub(crate) fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+        Some(quoted) => {
+            let mut unquoted = String::with_capacity(quoted.len());
+            let mut chars = quoted.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => unquoted.extend(chars.next()),
+                    c => unquoted.push(c),
+                }
+            }
+            unquoted
+        }
+        None => value.to_string(),
+    }
+}
+
+/// The response type a `ResponseDecoder` deserializes the body into.
+///
+/// Decoders don't know the response type, so they pass a `serde::Deserializer` over the body to
+/// [`deserialize`](DecodeSeed::deserialize) instead of returning a value.
+pub struct DecodeSeed<'a> {
+    seed: &'a mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> Result<(), erased_serde::Error>,
+}
+
+impl DecodeSeed<'_> {
+    /// Deserializes the response type from the deserializer.
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.seed)(&mut erased).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Decodes response bodies of one or more media types.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{ApiClient, ApiForgeError, ApiResult, DecodeSeed, MediaType, ResponseDecoder};
+///
+/// /// Decodes YAML-like `key: value` lines as a JSON object of strings.
+/// struct KeyValueDecoder;
+///
+/// impl ResponseDecoder for KeyValueDecoder {
+///     fn decode(&self, body: &[u8], _media_type: &MediaType, seed: DecodeSeed<'_>) -> ApiResult<()> {
+///         let text = String::from_utf8_lossy(body);
+///         let object = text
+///             .lines()
+///             .filter_map(|line| line.split_once(':'))
+///             .map(|(key, value)| (key.trim().to_string(), value.trim().into()))
+///             .collect::<serde_json::Map<_, _>>();
+///         seed.deserialize(serde_json::Value::Object(object))
+///             .map_err(ApiForgeError::JsonParseError)
+///     }
+/// }
+///
+/// let client = ApiClient::builder("https://api.example.com")
+///     .decoder("text/x-key-value", KeyValueDecoder)
+///     .build();
+/// ```
+pub trait ResponseDecoder: Send + Sync {
+    /// Decodes the body by passing a deserializer over it to the seed.
+    fn decode(&self, body: &[u8], media_type: &MediaType, seed: DecodeSeed<'_>) -> ApiResult<()>;
+}
+
+/// Decodes JSON bodies with `serde_json`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonDecoder;
+
+impl ResponseDecoder for JsonDecoder {
+    fn decode(&self, body: &[u8], media_type: &MediaType, seed: DecodeSeed<'_>) -> ApiResult<()> {
+        debug!("Parsing response as JSON");
+        let body = utf8_body(body, media_type)?;
+        let mut deserializer = serde_json::Deserializer::from_slice(&body);
+        seed.deserialize(&mut deserializer)
+            .and_then(|()| deserializer.end())
+            .map_err(|e| {
+                error!("JSON parsing error: {}", e);
+                ApiForgeError::JsonParseError(e)
+            })
+    }
+}
+
+/// The decoders used to parse response bodies, keyed by media type.
+///
+/// Decoders are registered for one of:
+///
+/// - a media type such as `application/json`, matching it with any parameters,
+/// - a structured syntax suffix such as `+json`, matching e.g. `application/problem+json`,
+/// - a top-level type such as `text/*`.
+///
+/// A response uses the decoder of its exact media type, then of its suffix, then of its
+/// top-level type. The JSON and XML decoders transcode bodies from their `charset` to UTF-8 with
+/// [`utf8_body`], which custom text decoders can use too. Responses without a matching decoder are decoded by the fallback decoder, if
+/// any, and otherwise fail with `UnsupportedContentType`. A missing `Content-Type` is treated as
+/// `application/octet-stream`.
+///
+/// Empty bodies aren't passed to decoders: they are decoded as `()`, `None` or `EmptyResponse`
+/// and fail for other response types, or for all types if the registry is
+/// [strict](DecoderRegistry::set_strict).
+///
+/// The default registry decodes `application/json`, `+json` and `text/plain` as JSON,
+/// `application/xml`, `text/xml` and `+xml` as XML, the MessagePack, CBOR and Protobuf types when
+/// their features are enabled, and falls back to JSON.
+#[derive(Clone)]
+pub struct DecoderRegistry {
+    decoders: HashMap<String, Arc<dyn ResponseDecoder>>,
+    fallback: Option<Arc<dyn ResponseDecoder>>,
+    strict: bool,
+    error_type: Option<ErrorType>,
+}
+
+impl DecoderRegistry {
+    /// Creates a registry without decoders or fallback.
+    pub fn empty() -> Self {
+        Self {
+            decoders: HashMap::new(),
+            fallback: None,
+            strict: false,
+            error_type: None,
+        }
+    }
+
+    /// Registers the decoder for a media type, suffix or top-level type, replacing any decoder
+    /// registered for it before.
+    pub fn register(&mut self, media_type: &str, decoder: impl ResponseDecoder + 'static) -> &mut Self {
+        self.register_arc(media_type, Arc::new(decoder))
+    }
+
+    fn register_arc(&mut self, media_type: &str, decoder: Arc<dyn ResponseDecoder>) -> &mut Self {
+        self.decoders.insert(media_type.trim().to_lowercase(), decoder);
+        self
+    }
+
+    /// Sets the decoder used for responses without a matching decoder.
+    pub fn set_fallback(&mut self, decoder: impl ResponseDecoder + 'static) -> &mut Self {
+        self.fallback = Some(Arc::new(decoder));
+        self
+    }
+
+    /// Removes the fallback decoder, so responses without a matching decoder fail.
+    pub fn clear_fallback(&mut self) -> &mut Self {
+        self.fallback = None;
+        self
+    }
+
+    /// Makes empty bodies of successful responses, other than `204 No Content`, fail with
+    /// `EmptyBody` even if the response type accepts them.
+    ///
+    /// Responses to `HEAD` requests have no body, so they fail too unless the status is 204.
+    pub fn set_strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Returns true if empty bodies of successful responses, other than `204 No Content`, fail.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Sets the type error bodies are deserialized into for requests without their own
+    /// `ERROR_TYPE`.
+    pub fn set_error_type(&mut self, error_type: ErrorType) -> &mut Self {
+        self.error_type = Some(error_type);
+        self
+    }
+
+    /// Returns the type error bodies are deserialized into, if set.
+    pub fn error_type(&self) -> Option<ErrorType> {
+        self.error_type
+    }
+
+    /// Returns the decoder registered for the media type, without the fallback.
+    pub fn find(&self, media_type: &MediaType) -> Option<&dyn ResponseDecoder> {
+        let suffix = media_type.suffix().map(|suffix| format!("+{}", suffix));
+        let top_level = format!("{}/*", media_type.type_name());
+
+        let decoder = [Some(media_type.essence()), suffix.as_deref(), Some(top_level.as_str())]
+            .into_iter()
+            .flatten()
+            .find_map(|key| self.decoders.get(key));
+        decoder.map(Arc::as_ref)
+    }
+
+    /// Decodes a body with the decoder matching the `Content-Type` value.
+    pub fn decode<Res: DeserializeOwned>(&self, content_type: Option<&str>, body: &[u8]) -> ApiResult<Res> {
+        let media_type = content_type
+            .and_then(MediaType::parse)
+            .unwrap_or_else(|| {
+                debug!("Missing or invalid Content-Type: {:?}, using application/octet-stream", content_type);
+                MediaType::parse("application/octet-stream").expect("valid media type")
+            });
+        debug!("Content-Type: {}", media_type);
+
+        if let Some(decoder) = self.find(&media_type) {
+            return decode_with(decoder, body, &media_type);
+        }
+
+        let unsupported = || ApiForgeError::UnsupportedContentType(content_type.unwrap_or_default().to_string());
+        match &self.fallback {
+            Some(fallback) => {
+                debug!("No decoder for {}, trying the fallback decoder", media_type.essence());
+                decode_with(fallback.as_ref(), body, &media_type).map_err(|e| {
+                    error!("Failed to parse unknown content type: {}", e);
+                    unsupported()
+                })
+            }
+            None => {
+                error!("No decoder for {}", media_type.essence());
+                Err(unsupported())
+            }
+        }
+    }
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        let json: Arc<dyn ResponseDecoder> = Arc::new(JsonDecoder);
+        for media_type in ["application/json", "+json", "text/plain"] {
+            registry.register_arc(media_type, json.clone());
+        }
+        let xml: Arc<dyn ResponseDecoder> = Arc::new(crate::xml::XmlDecoder);
+        for media_type in ["application/xml", "text/xml", "+xml"] {
+            registry.register_arc(media_type, xml.clone());
+        }
+        #[cfg(feature = "msgpack")]
+        {
+            let msgpack: Arc<dyn ResponseDecoder> = Arc::new(crate::msgpack::MsgPackDecoder);
+            for media_type in ["application/msgpack", "application/x-msgpack", "application/vnd.msgpack"] {
+                registry.register_arc(media_type, msgpack.clone());
+            }
+        }
+        #[cfg(feature = "cbor")]
+        {
+            let cbor: Arc<dyn ResponseDecoder> = Arc::new(crate::cbor::CborDecoder);
+            for media_type in ["application/cbor", "+cbor"] {
+                registry.register_arc(media_type, cbor.clone());
+            }
+        }
+        #[cfg(feature = "protobuf")]
+        {
+            let protobuf: Arc<dyn ResponseDecoder> = Arc::new(crate::protobuf::ProtobufDecoder);
+            for media_type in ["application/x-protobuf", "application/protobuf"] {
+                registry.register_arc(media_type, protobuf.clone());
+            }
+        }
+        registry.fallback = Some(json);
+        registry
+    }
+}
+
+impl fmt::Debug for DecoderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut media_types = self.decoders.keys().collect::<Vec<_>>();
+        media_types.sort();
+        f.debug_struct("DecoderRegistry")
+            .field("media_types", &media_types)
+            .field("fallback", &self.fallback.is_some())
+            .field("strict", &self.strict)
+            .field("error_type", &self.error_type.is_some())
+            .finish()
+    }
+}
+
+/// Returns the process-wide default registry used outside of a client.
+pub(crate) fn default_decoders() -> &'static DecoderRegistry {
+    static DECODERS: OnceLock<DecoderRegistry> = OnceLock::new();
+    DECODERS.get_or_init(DecoderRegistry::default)
+}
+
+/// Runs the decoder, deserializing the response type from the deserializer it provides.
+fn decode_with<Res: DeserializeOwned>(
+    decoder: &dyn ResponseDecoder,
+    body: &[u8],
+    media_type: &MediaType,
+) -> ApiResult<Res> {
+    let mut value = None;
+    let mut seed = |deserializer: &mut dyn erased_serde::Deserializer<'_>| {
+        value = Some(erased_serde::deserialize::<Res>(deserializer)?);
+        Ok(())
+    };
+    decoder.decode(body, media_type, DecodeSeed { seed: &mut seed })?;
+    value.ok_or_else(|| ApiForgeError::decode(format!("The decoder for {} returned no value", media_type.essence())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    /// Decodes any body as the name of the decoder.
+    struct Named(&'static str);
+
+    impl ResponseDecoder for Named {
+        fn decode(&self, _body: &[u8], _media_type: &MediaType, seed: DecodeSeed<'_>) -> ApiResult<()> {
+            seed.deserialize(serde_json::Value::from(self.0))
+                .map_err(ApiForgeError::JsonParseError)
+        }
+    }
+
+    fn registry() -> DecoderRegistry {
+        let mut registry = DecoderRegistry::empty();
+        registry
+            .register("application/vnd.api+json", Named("exact"))
+            .register("+json", Named("suffix"))
+            .register("text/*", Named("top-level"));
+        registry
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Greeting {
+        text: String,
+    }
+
+    #[test]
+    fn decoders_match_exact_then_suffix_then_top_level() {
+        let registry = registry();
+        let decode = |content_type| registry.decode::<String>(Some(content_type), b"{}").unwrap();
+
+        assert_eq!(decode("application/vnd.api+json"), "exact");
+        assert_eq!(decode("Application/VND.API+JSON; charset=utf-8"), "exact");
+        assert_eq!(decode("application/problem+json"), "suffix");
+        assert_eq!(decode("text/csv"), "top-level");
+        assert_eq!(decode("text/vnd.custom+json"), "suffix");
+    }
+
+    #[test]
+    fn unmatched_media_types_use_the_fallback() {
+        let mut registry = registry();
+        assert!(matches!(
+            registry.decode::<String>(Some("image/png"), b"{}"),
+            Err(ApiForgeError::UnsupportedContentType(content_type)) if content_type == "image/png"
+        ));
+
+        registry.set_fallback(Named("fallback"));
+        assert_eq!(registry.decode::<String>(Some("image/png"), b"{}").unwrap(), "fallback");
+        assert_eq!(registry.decode::<String>(None, b"{}").unwrap(), "fallback");
+        assert_eq!(registry.decode::<String>(Some("not a media type"), b"{}").unwrap(), "fallback");
+    }
+
+    #[test]
+    fn media_types_parse_parameters() {
+        let media_type = MediaType::parse("Application/Problem+JSON; Charset=\"UTF-8\"; q=1").unwrap();
+        assert_eq!(media_type.essence(), "application/problem+json");
+        assert_eq!(media_type.type_name(), "application");
+        assert_eq!(media_type.subtype(), "problem+json");
+        assert_eq!(media_type.suffix(), Some("json"));
+        assert_eq!(media_type.charset(), Some("UTF-8"));
+        assert_eq!(media_type.parameter("q"), Some("1"));
+
+        assert_eq!(MediaType::parse("json"), None);
+        assert_eq!(MediaType::parse("text/"), None);
+    }
+
+    #[test]
+    fn bodies_are_transcoded_from_their_charset() {
+        let registry = DecoderRegistry::default();
+        let latin1 = b"{\"text\":\"Gr\xfc\xdfe\"}";
+        let greeting: Greeting = registry
+            .decode(Some("application/json; charset=ISO-8859-1"), latin1)
+            .unwrap();
+        assert_eq!(greeting.text, "Grüße");
+
+        let utf16 = "{\"text\":\"Grüße\"}"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        let greeting: Greeting = registry.decode(Some("application/json; charset=utf-16le"), &utf16).unwrap();
+        assert_eq!(greeting.text, "Grüße");
+
+        let xml = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><Greeting><text>Gr\xfc\xdfe</text></Greeting>";
+        let greeting: Greeting = registry.decode(Some("application/xml; charset=iso-8859-1"), xml).unwrap();
+        assert_eq!(greeting.text, "Grüße");
+    }
+
+    #[test]
+    fn unsupported_charsets_are_rejected() {
+        let registry = DecoderRegistry::default();
+        let result = registry.decode::<Greeting>(Some("application/json; charset=klingon"), b"{\"text\":\"hi\"}");
+        assert!(
+            matches!(&result, Err(ApiForgeError::UnsupportedContentType(content_type)) if content_type.contains("klingon")),
+            "{:?}",
+            result
+        );
+
+        let result = registry.decode::<Greeting>(Some("application/json; charset=utf-16le"), b"\x00\xd8");
+        assert!(matches!(result, Err(ApiForgeError::DecodeError(_))), "{:?}", result);
+    }
+
+    #[cfg(any(feature = "msgpack", feature = "cbor"))]
+    #[test]
+    fn invalid_binary_bodies_are_decode_errors() {
+        let registry = DecoderRegistry::default();
+        for content_type in [
+            #[cfg(feature = "msgpack")]
+            "application/msgpack",
+            #[cfg(feature = "cbor")]
+            "application/cbor",
+        ] {
+            let result = registry.decode::<Greeting>(Some(content_type), b"\xc1\xff");
+            assert!(matches!(result, Err(ApiForgeError::DecodeError(_))), "{}: {:?}", content_type, result);
+        }
+    }
+
+    #[test]
+    fn utf8_bodies_are_borrowed() {
+        let media_type = MediaType::parse("text/plain; charset=utf-8").unwrap();
+        assert!(matches!(utf8_body(b"hi", &media_type), Ok(Cow::Borrowed(b"hi"))));
+        let media_type = MediaType::parse("text/plain").unwrap();
+        assert!(matches!(utf8_body(b"hi", &media_type), Ok(Cow::Borrowed(b"hi"))));
+    }
+}
@@ -0,0 +1,462 @@
+use crate::auth::{expect_method, hex_encode, sensitive_value, AuthProvider};
+use crate::decoder::{split_unquoted, unquote};
+use crate::error::ApiForgeError;
+use crate::traits::AuthenticationMethod;
+use crate::ApiResult;
+use async_trait::async_trait;
+use md5::Md5;
+use reqwest::header::{HeaderMap, AUTHORIZATION, WWW_AUTHENTICATE};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::Mutex;
+use tracing::debug;
+
+/// Hash algorithm of a Digest challenge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Algorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl Algorithm {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "MD5" => Some(Self::Md5),
+            "MD5-SESS" => Some(Self::Md5Sess),
+            "SHA-256" => Some(Self::Sha256),
+            "SHA-256-SESS" => Some(Self::Sha256Sess),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Sess => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn is_session(self) -> bool {
+        matches!(self, Self::Md5Sess | Self::Sha256Sess)
+    }
+
+    /// Hashes the data and returns the lowercase hex digest.
+    fn hash(self, data: &str) -> String {
+        match self {
+            Self::Md5 | Self::Md5Sess => hex_encode(&Md5::digest(data)),
+            Self::Sha256 | Self::Sha256Sess => hex_encode(&Sha256::digest(data)),
+        }
+    }
+}
+
+/// A Digest challenge of a `WWW-Authenticate` header, as defined by RFC 7616.
+#[derive(Debug, Clone)]
+struct Challenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: Algorithm,
+    /// Whether the server supports `qop=auth`. Absent for servers following RFC 2069.
+    qop: bool,
+    userhash: bool,
+    stale: bool,
+}
+
+impl Challenge {
+    /// Creates a challenge from its parameters, returning `None` if it isn't supported.
+    fn from_params(params: &[(String, String)]) -> Option<Self> {
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(param, _)| param == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let flag = |name: &str| param(name).is_some_and(|value| value.eq_ignore_ascii_case("true"));
+
+        let qop = match param("qop") {
+            Some(qop) => {
+                if !qop.split(',').any(|qop| qop.trim().eq_ignore_ascii_case("auth")) {
+                    debug!("Digest challenge only offers qop={}, which is not supported", qop);
+                    return None;
+                }
+                true
+            }
+            None => false,
+        };
+        let algorithm = match param("algorithm") {
+            Some(algorithm) => match Algorithm::parse(algorithm) {
+                Some(algorithm) => algorithm,
+                None => {
+                    debug!("Digest challenge uses algorithm {}, which is not supported", algorithm);
+                    return None;
+                }
+            },
+            None => Algorithm::Md5,
+        };
+
+        Some(Self {
+            realm: param("realm")?.to_string(),
+            nonce: param("nonce")?.to_string(),
+            opaque: param("opaque").map(str::to_string),
+            algorithm,
+            qop,
+            userhash: flag("userhash"),
+            stale: flag("stale"),
+        })
+    }
+
+    /// Returns the strongest supported Digest challenge of the response headers.
+    fn select(headers: &HeaderMap) -> Option<Self> {
+        parse_digest_challenges(headers)
+            .iter()
+            .filter_map(|params| Self::from_params(params))
+            .max_by_key(|challenge| challenge.algorithm)
+    }
+
+    /// Creates the `Authorization` header value answering the challenge with the client nonce.
+    fn authorization(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        nonce_count: u32,
+        cnonce: &str,
+    ) -> String {
+        let algorithm = self.algorithm;
+        let nc = format!("{:08x}", nonce_count);
+
+        let mut ha1 = algorithm.hash(&format!("{}:{}:{}", username, self.realm, password));
+        if algorithm.is_session() {
+            ha1 = algorithm.hash(&format!("{}:{}:{}", ha1, self.nonce, cnonce));
+        }
+        let ha2 = algorithm.hash(&format!("{}:{}", method, uri));
+        let response = if self.qop {
+            algorithm.hash(&format!("{}:{}:{}:{}:auth:{}", ha1, self.nonce, nc, cnonce, ha2))
+        } else {
+            algorithm.hash(&format!("{}:{}:{}", ha1, self.nonce, ha2))
+        };
+
+        let username = if self.userhash {
+            algorithm.hash(&format!("{}:{}", username, self.realm))
+        } else {
+            username.to_string()
+        };
+
+        let mut value = format!(
+            "Digest username={}, realm={}, uri={}, algorithm={}, nonce={}, response={}",
+            quote(&username),
+            quote(&self.realm),
+            quote(uri),
+            algorithm.name(),
+            quote(&self.nonce),
+            quote(&response),
+        );
+        if self.qop {
+            value.push_str(&format!(", qop=auth, nc={}, cnonce={}", nc, quote(cnonce)));
+        }
+        if let Some(opaque) = &self.opaque {
+            value.push_str(&format!(", opaque={}", quote(opaque)));
+        }
+        if self.userhash {
+            value.push_str(", userhash=true");
+        }
+        value
+    }
+}
+
+/// Returns the parameters of the Digest challenges in `WWW-Authenticate` headers.
+///
+/// A header can hold several challenges, e.g. `Digest realm="a", nonce="b", Basic realm="a"`, so
+/// a new challenge starts at each item beginning with a scheme rather than a parameter.
+fn parse_digest_challenges(headers: &HeaderMap) -> Vec<Vec<(String, String)>> {
+    let mut challenges = Vec::new();
+    for value in headers.get_all(WWW_AUTHENTICATE) {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+
+        let mut current: Option<Vec<(String, String)>> = None;
+        for item in split_unquoted(value, ',') {
+            let mut item = item.trim();
+            let (first, rest) = item.split_once(char::is_whitespace).unwrap_or((item, ""));
+            if !first.is_empty() && !first.contains('=') && !rest.trim_start().starts_with('=') {
+                challenges.extend(current.take());
+                if first.eq_ignore_ascii_case("digest") {
+                    current = Some(Vec::new());
+                }
+                item = rest.trim();
+            }
+
+            if let (Some(params), Some((name, value))) = (current.as_mut(), item.split_once('=')) {
+                params.push((name.trim().to_ascii_lowercase(), unquote(value.trim())));
+            }
+        }
+        challenges.extend(current);
+    }
+    challenges
+}
+
+/// Quotes a parameter value, escaping quotes and backslashes.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Creates a random client nonce.
+fn cnonce() -> ApiResult<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(ApiForgeError::auth)?;
+    Ok(hex_encode(&bytes))
+}
+
+/// The challenge answered and the number of requests sent with its nonce.
+struct DigestState {
+    challenge: Challenge,
+    nonce_count: u32,
+}
+
+/// Sends a username and password with HTTP Digest authentication, as defined by RFC 7616.
+///
+/// The first request is sent without credentials. Its `401 Unauthorized` challenge is answered
+/// by sending the request once more, and the nonce is cached for later requests until the server
+/// issues a new one. Supports the `MD5` and `SHA-256` algorithms, their `-sess` variants and
+/// `qop=auth`, preferring `SHA-256` if the server offers several.
+///
+/// The cached nonce is shared by all requests, so use one provider per server.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{ApiClient, DigestAuth};
+///
+/// let client = ApiClient::builder("http://camera.local")
+///     .auth(DigestAuth::new("admin", "secret"))
+///     .build();
+/// ```
+pub struct DigestAuth {
+    username: String,
+    password: String,
+    state: Mutex<Option<DigestState>>,
+}
+
+impl DigestAuth {
+    /// Creates a provider sending the username and password.
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+            state: Mutex::new(None),
+        }
+    }
+}
+
+impl DigestAuth {
+    /// Answers the cached challenge, counting the request against its nonce.
+    fn authorize(&self, request: &mut reqwest::Request) -> ApiResult<()> {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        let Some(state) = state.as_mut() else {
+            debug!("No Digest challenge received yet, sending the request without credentials");
+            return Ok(());
+        };
+
+        let url = request.url();
+        let uri = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        state.nonce_count += 1;
+        let value = state.challenge.authorization(
+            &self.username,
+            &self.password,
+            request.method().as_str(),
+            &uri,
+            state.nonce_count,
+            &cnonce()?,
+        );
+        request.headers_mut().insert(AUTHORIZATION, sensitive_value(&value)?);
+        Ok(())
+    }
+
+    /// Caches the challenge of a `401 Unauthorized` response, returning whether the request
+    /// should be sent once more to answer it.
+    fn accept_challenge(&self, headers: &HeaderMap) -> bool {
+        let Some(challenge) = Challenge::select(headers) else {
+            debug!("Response has no supported Digest challenge");
+            return false;
+        };
+
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        // A fresh challenge with the nonce already answered means the credentials are wrong.
+        let rejected = !challenge.stale
+            && state
+                .as_ref()
+                .is_some_and(|state| state.challenge.nonce == challenge.nonce);
+        if rejected {
+            debug!("Digest credentials were rejected");
+            return false;
+        }
+
+        debug!("Answering Digest challenge for realm {}", challenge.realm);
+        *state = Some(DigestState {
+            challenge,
+            nonce_count: 0,
+        });
+        true
+    }
+}
+
+impl fmt::Debug for DigestAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestAuth")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl AuthProvider for DigestAuth {
+    async fn apply(&self, request: &mut reqwest::Request, method: AuthenticationMethod) -> ApiResult<()> {
+        expect_method(AuthenticationMethod::Digest, method)?;
+        self.authorize(request)
+    }
+
+    async fn on_unauthorized(&self, method: AuthenticationMethod, headers: &HeaderMap) -> ApiResult<bool> {
+        expect_method(AuthenticationMethod::Digest, method)?;
+        Ok(self.accept_challenge(headers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    /// The `WWW-Authenticate` challenge of the RFC 7616 §3.9.1 example.
+    fn rfc_challenge(algorithm: &str) -> HeaderMap {
+        www_authenticate(&[&format!(
+            "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", algorithm={}, \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"",
+            algorithm
+        )])
+    }
+
+    fn www_authenticate(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(WWW_AUTHENTICATE, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn rfc_authorization(algorithm: &str) -> String {
+        Challenge::select(&rfc_challenge(algorithm)).unwrap().authorization(
+            "Mufasa",
+            "Circle of Life",
+            "GET",
+            "/dir/index.html",
+            1,
+            "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+        )
+    }
+
+    #[test]
+    fn md5_response_matches_rfc_7616() {
+        let authorization = rfc_authorization("MD5");
+        assert!(
+            authorization.contains("response=\"8ca523f5e9506fed4657c9700eebdbec\""),
+            "{}",
+            authorization
+        );
+        assert!(authorization.contains("qop=auth, nc=00000001"), "{}", authorization);
+        assert!(
+            authorization.contains("opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""),
+            "{}",
+            authorization
+        );
+    }
+
+    #[test]
+    fn sha256_response_matches_rfc_7616() {
+        let authorization = rfc_authorization("SHA-256");
+        assert!(
+            authorization.contains("response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\""),
+            "{}",
+            authorization
+        );
+        assert!(authorization.contains("algorithm=SHA-256"), "{}", authorization);
+    }
+
+    #[test]
+    fn quoted_commas_stay_in_their_parameter() {
+        let headers = www_authenticate(&[r#"Digest realm="users, admins", nonce="a,b", qop="auth,auth-int""#]);
+        let challenge = Challenge::select(&headers).unwrap();
+        assert_eq!(challenge.realm, "users, admins");
+        assert_eq!(challenge.nonce, "a,b");
+        assert!(challenge.qop);
+        assert_eq!(challenge.algorithm, Algorithm::Md5);
+    }
+
+    #[test]
+    fn strongest_of_several_challenges_is_selected() {
+        let headers = www_authenticate(&[
+            r#"Basic realm="api", Digest realm="api", nonce="md5-nonce", algorithm=MD5, Digest realm="api", nonce="sha-nonce", algorithm=SHA-256"#,
+            r#"Digest realm="api", nonce="unsupported", algorithm=SHA-512-256"#,
+        ]);
+        assert_eq!(parse_digest_challenges(&headers).len(), 3);
+        let challenge = Challenge::select(&headers).unwrap();
+        assert_eq!(challenge.nonce, "sha-nonce");
+        assert_eq!(challenge.algorithm, Algorithm::Sha256);
+
+        let headers = www_authenticate(&[r#"Basic realm="api""#, r#"Bearer realm="api", error="invalid_token""#]);
+        assert!(Challenge::select(&headers).is_none());
+    }
+
+    #[test]
+    fn stale_nonces_are_answered_again() {
+        let auth = DigestAuth::new("Mufasa", "Circle of Life");
+        let challenge = r#"Digest realm="api", nonce="first", qop="auth""#;
+        assert!(auth.accept_challenge(&www_authenticate(&[challenge])));
+        assert!(
+            !auth.accept_challenge(&www_authenticate(&[challenge])),
+            "an answered nonce means the credentials are wrong"
+        );
+
+        let stale = www_authenticate(&[r#"Digest realm="api", nonce="first", qop="auth", stale=TRUE"#]);
+        assert!(Challenge::select(&stale).unwrap().stale);
+        assert!(auth.accept_challenge(&stale));
+        assert!(auth.accept_challenge(&www_authenticate(&[r#"Digest realm="api", nonce="second""#])));
+    }
+
+    #[test]
+    fn nonce_count_increments_while_the_nonce_is_reused() {
+        let auth = DigestAuth::new("Mufasa", "Circle of Life");
+        let nonce_counts = |auth: &DigestAuth| {
+            (0..2)
+                .map(|_| {
+                    let url = "http://api.local/dir/index.html?page=2".parse().unwrap();
+                    let mut request = reqwest::Request::new(reqwest::Method::GET, url);
+                    auth.authorize(&mut request).unwrap();
+                    let authorization = request.headers()[AUTHORIZATION].to_str().unwrap().to_string();
+                    assert!(authorization.contains("uri=\"/dir/index.html?page=2\""), "{}", authorization);
+                    authorization.split(", ").find(|param| param.starts_with("nc=")).unwrap().to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut request = reqwest::Request::new(reqwest::Method::GET, "http://api.local/".parse().unwrap());
+        auth.authorize(&mut request).unwrap();
+        assert!(request.headers().get(AUTHORIZATION).is_none(), "no challenge received yet");
+
+        auth.accept_challenge(&www_authenticate(&[r#"Digest realm="api", nonce="first", qop="auth""#]));
+        assert_eq!(nonce_counts(&auth), ["nc=00000001", "nc=00000002"]);
+
+        auth.accept_challenge(&www_authenticate(&[r#"Digest realm="api", nonce="second", qop="auth""#]));
+        assert_eq!(nonce_counts(&auth), ["nc=00000001", "nc=00000002"], "a new nonce starts over");
+    }
+}
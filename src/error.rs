@@ -1,7 +1,15 @@
+use crate::decoder::DecoderRegistry;
+use crate::problem::ProblemDetails;
 use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fmt;
 use thiserror::Error;
 
+/// Boxed error type used for errors from transports and streaming bodies.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
 /// Error type for API Forge operations.
 #[derive(Debug, Error)]
 pub enum ApiForgeError {
@@ -16,14 +24,55 @@ pub enum ApiForgeError {
         message: String,
     },
 
+    /// A successful response without a body, for a response type that requires one or with a
+    /// strict `DecoderRegistry`.
+    #[error("Server returned an empty body: {status}")]
+    EmptyBody { status: StatusCode },
+
+    /// Error response from the server, with the body deserialized into the error type of the
+    /// request or client.
+    #[error("Server returned error: {status} - {body}")]
+    Api {
+        status: StatusCode,
+        body: ErrorBody,
+    },
+
+    /// Error response from the server with `application/problem+json` problem details.
+    #[error("Server returned problem: {status} - {problem}")]
+    Problem {
+        status: StatusCode,
+        problem: Box<ProblemDetails>,
+    },
+
+    /// Error from a transport other than reqwest, or from a streaming body.
+    #[error("Transport failed: {0}")]
+    TransportError(#[source] BoxError),
+
+    /// Error from an `AuthProvider` applying credentials.
+    #[error("Authentication failed: {0}")]
+    AuthError(#[source] BoxError),
+
     /// Error parsing the response body (e.g., JSON parsing).
     #[error("Failed to parse response: {0}")]
     ParseError(reqwest::Error),
 
+    /// Error parsing JSON content.
+    #[error("Failed to parse JSON response: {0}")]
+    JsonParseError(#[from] serde_json::Error),
+
     /// Error parsing XML content.
     #[error("Failed to parse XML response: {0}")]
     XmlParseError(#[from] serde_xml_rust::Error),
 
+    /// Error from a response decoder, e.g. a MessagePack, CBOR or Protobuf body that doesn't
+    /// parse.
+    #[error("Failed to decode response: {0}")]
+    DecodeError(#[source] BoxError),
+
+    /// Error serializing the request body.
+    #[error("Failed to serialize request body: {0}")]
+    SerializeError(#[source] BoxError),
+
     /// Unsupported or unknown content type.
     #[error("Unsupported content type: {0}")]
     UnsupportedContentType(String),
@@ -47,6 +96,26 @@ impl ApiForgeError {
         }
     }
 
+    /// Creates a new `TransportError` from any error.
+    pub fn transport(err: impl Into<BoxError>) -> Self {
+        Self::TransportError(err.into())
+    }
+
+    /// Creates a new `AuthError` from any error.
+    pub fn auth(err: impl Into<BoxError>) -> Self {
+        Self::AuthError(err.into())
+    }
+
+    /// Creates a new `DecodeError` from any error.
+    pub fn decode(err: impl Into<BoxError>) -> Self {
+        Self::DecodeError(err.into())
+    }
+
+    /// Creates a new `SerializeError` from any error.
+    pub fn serialize(err: impl Into<BoxError>) -> Self {
+        Self::SerializeError(err.into())
+    }
+
     /// Creates a new `ValidationError` with the given message.
     pub fn validation(msg: impl Into<String>) -> Self {
         Self::ValidationError(msg.into())
@@ -60,12 +129,30 @@ impl ApiForgeError {
     /// Returns the status code if this error is a response error.
     pub fn status_code(&self) -> Option<StatusCode> {
         match self {
-            Self::ResponseError { status, .. } => Some(*status),
+            Self::ResponseError { status, .. } | Self::Api { status, .. } | Self::Problem { status, .. } => {
+                Some(*status)
+            }
             Self::ReqwestError(err) => err.status(),
             _ => None,
         }
     }
 
+    /// Returns the deserialized error body if this is an `Api` error with a body of type `E`.
+    pub fn api_error<E: Any>(&self) -> Option<&E> {
+        match self {
+            Self::Api { body, .. } => body.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the problem details if this is a `Problem` error.
+    pub fn problem(&self) -> Option<&ProblemDetails> {
+        match self {
+            Self::Problem { problem, .. } => Some(problem),
+            _ => None,
+        }
+    }
+
     /// Returns true if this error represents a 4xx client error.
     pub fn is_client_error(&self) -> bool {
         self.status_code()
@@ -81,7 +168,168 @@ impl ApiForgeError {
     }
 }
 
+/// The body of an `ApiForgeError::Api` error, deserialized into the error type.
+///
+/// Use [`downcast_ref`](ErrorBody::downcast_ref) or `ApiForgeError::api_error` to access it.
+/// The raw body is kept as text and used as the error message.
+pub struct ErrorBody {
+    value: Box<dyn ErasedErrorBody>,
+    text: String,
+}
+
+impl ErrorBody {
+    /// Creates a body from a deserialized value and the raw body text.
+    pub fn new<E>(value: E, text: impl Into<String>) -> Self
+    where
+        E: fmt::Debug + Send + Sync + 'static,
+    {
+        Self {
+            value: Box::new(value),
+            text: text.into(),
+        }
+    }
+
+    /// Returns the raw body as text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns true if the body was deserialized into `E`.
+    pub fn is<E: Any>(&self) -> bool {
+        (*self.value).as_any().is::<E>()
+    }
+
+    /// Returns the body if it was deserialized into `E`.
+    pub fn downcast_ref<E: Any>(&self) -> Option<&E> {
+        (*self.value).as_any().downcast_ref()
+    }
+
+    /// Takes the body if it was deserialized into `E`, or returns it unchanged.
+    pub fn downcast<E: Any>(self) -> Result<E, Self> {
+        if self.is::<E>() {
+            let value = self.value.into_any().downcast::<E>().expect("type was checked");
+            Ok(*value)
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Debug for ErrorBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.value, f)
+    }
+}
+
+impl fmt::Display for ErrorBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// A value that can be stored in an `ErrorBody`.
+trait ErasedErrorBody: fmt::Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<E: fmt::Debug + Send + Sync + 'static> ErasedErrorBody for E {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// The type error response bodies are deserialized into.
+///
+/// Set per request with `ApiRequest::ERROR_TYPE`, or the `error_type` attribute of the derive,
+/// and per client with `ApiClientBuilder::error_type`. `application/problem+json` bodies are
+/// returned as `Problem` errors, other error bodies are decoded with the decoder for their
+/// `Content-Type`, and bodies that can't be decoded are returned as a `ResponseError` with the
+/// raw text.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{ApiForgeError, Request};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Debug)]
+/// struct MyApiError {
+///     code: u32,
+///     reason: String,
+/// }
+///
+/// #[derive(Serialize, Debug, Request)]
+/// #[request(endpoint = "/users", error_type = "MyApiError")]
+/// struct ListUsers;
+///
+/// fn reason(err: &ApiForgeError) -> Option<&str> {
+///     err.api_error::<MyApiError>().map(|body| body.reason.as_str())
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct ErrorType {
+    decode: fn(StatusCode, Option<&str>, &[u8], &DecoderRegistry) -> Option<ApiForgeError>,
+}
+
+impl ErrorType {
+    /// Deserializes error bodies into `E`.
+    pub const fn of<E>() -> Self
+    where
+        E: DeserializeOwned + fmt::Debug + Send + Sync + 'static,
+    {
+        Self {
+            decode: decode_error_body::<E>,
+        }
+    }
+
+    /// Decodes an error body, returning `None` if it doesn't parse.
+    pub(crate) fn decode(
+        &self,
+        status: StatusCode,
+        content_type: Option<&str>,
+        body: &[u8],
+        decoders: &DecoderRegistry,
+    ) -> Option<ApiForgeError> {
+        (self.decode)(status, content_type, body, decoders)
+    }
+}
+
+impl fmt::Debug for ErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorType").finish_non_exhaustive()
+    }
+}
+
+fn decode_error_body<E>(
+    status: StatusCode,
+    content_type: Option<&str>,
+    body: &[u8],
+    decoders: &DecoderRegistry,
+) -> Option<ApiForgeError>
+where
+    E: DeserializeOwned + fmt::Debug + Send + Sync + 'static,
+{
+    match decoders.decode::<E>(content_type, body) {
+        Ok(value) => Some(ApiForgeError::Api {
+            status,
+            body: ErrorBody::new(value, String::from_utf8_lossy(body)),
+        }),
+        Err(err) => {
+            tracing::debug!("Failed to decode error body, keeping the raw text: {}", err);
+            None
+        }
+    }
+}
+
 /// A structure for standardized error responses from APIs.
+///
+/// Can be used as error type, e.g. `#[request(error_type = "api_forge::ErrorResponse")]`.
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct ErrorResponse {
     /// The error message.
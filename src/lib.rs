@@ -1,10 +1,60 @@
-#![feature(associated_type_defaults)]
-
+pub use crate::auth::*;
+#[cfg(feature = "blocking")]
+pub use crate::blocking::*;
+pub use crate::body::*;
+#[cfg(feature = "cbor")]
+pub use crate::cbor::*;
+pub use crate::client::*;
+pub use crate::decoder::*;
+pub use crate::digest::*;
 pub use crate::error::*;
+pub use crate::middleware::*;
+#[cfg(feature = "msgpack")]
+pub use crate::msgpack::*;
+pub use crate::multipart::*;
+pub use crate::oauth::*;
+pub use crate::problem::*;
+#[cfg(feature = "protobuf")]
+pub use crate::protobuf::*;
+pub use crate::rate_limit::*;
+pub use crate::response::*;
+pub use crate::retry::*;
+pub use crate::signing::*;
 pub use crate::traits::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::transport::*;
+pub use crate::xml::*;
 pub use api_forge_macro::Request;
+pub use async_trait::async_trait;
 
+pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod body;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod client;
+pub mod decoder;
+pub mod digest;
 pub mod error;
+pub mod middleware;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod multipart;
+pub mod oauth;
+pub mod problem;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+pub mod rate_limit;
+pub mod response;
+pub mod retry;
+pub mod signing;
+mod runtime;
 pub mod traits;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod transport;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod xml;
 
 pub type ApiResult<T, E = ApiForgeError> = Result<T, E>;
\ No newline at end of file
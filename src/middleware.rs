@@ -0,0 +1,89 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::{Transport, TransportRequest};
+use crate::ApiResult;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// A middleware wrapping the sending of requests by `ApiClient`.
+///
+/// Middleware receives the fully built `reqwest::Request` and the rest of the chain as `next`.
+/// It can change the request before calling `next.run(request)`, inspect or replace the
+/// response or error afterwards, or stop the call early by returning without calling `next`.
+///
+/// Middleware runs in the order it was registered on the client, once per attempt when the
+/// request is retried.
+///
+/// On `wasm32`, the returned futures are not `Send`, so implementations use
+/// `#[async_trait(?Send)]` there.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{async_trait, ApiClient, ApiResult, Middleware, Next};
+/// use reqwest::header::HeaderValue;
+///
+/// struct RequestId;
+///
+/// #[async_trait]
+/// impl Middleware for RequestId {
+///     async fn handle(&self, mut request: reqwest::Request, next: Next<'_>) -> ApiResult<reqwest::Response> {
+///         request
+///             .headers_mut()
+///             .insert("X-Request-ID", HeaderValue::from_static("generated-id"));
+///         let response = next.run(request).await;
+///         tracing::info!("Request finished: {:?}", response.as_ref().map(|r| r.status()));
+///         response
+///     }
+/// }
+///
+/// let client = ApiClient::builder("https://api.example.com")
+///     .middleware(RequestId)
+///     .build();
+/// ```
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Middleware: Send + Sync + 'static {
+    /// Handles a request, usually by calling `next.run(request)`.
+    async fn handle(&self, request: reqwest::Request, next: Next<'_>) -> ApiResult<reqwest::Response>;
+}
+
+/// The remaining middleware chain, ending in the client's transport.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    #[cfg(not(target_arch = "wasm32"))]
+    transport: &'a dyn Transport,
+    #[cfg(target_arch = "wasm32")]
+    transport: &'a reqwest::Client,
+    middleware: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn new(transport: &'a dyn Transport, middleware: &'a [Arc<dyn Middleware>]) -> Self {
+        Self { transport, middleware }
+    }
+
+    /// On `wasm32` requests are always sent with the fetch-based `reqwest::Client`.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn new(transport: &'a reqwest::Client, middleware: &'a [Arc<dyn Middleware>]) -> Self {
+        Self { transport, middleware }
+    }
+
+    /// Passes the request to the next middleware, or sends it if this is the end of the chain.
+    pub async fn run(self, request: reqwest::Request) -> ApiResult<reqwest::Response> {
+        match self.middleware.split_first() {
+            Some((current, rest)) => {
+                current
+                    .handle(request, Next::new(self.transport, rest))
+                    .await
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            None => {
+                let response = self.transport.send(TransportRequest::from(request)).await?;
+                Ok(response.into())
+            }
+            #[cfg(target_arch = "wasm32")]
+            None => Ok(self.transport.execute(request).await?),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::decoder::{DecodeSeed, MediaType, ResponseDecoder};
+use crate::error::ApiForgeError;
+use crate::ApiResult;
+use serde::Serialize;
+use tracing::{debug, error};
+
+/// Content type of MessagePack bodies.
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Creates the request body for `DataTransmissionMethod::MessagePack`.
+///
+/// Structs are encoded as maps with field names, like `rmp_serde::to_vec_named`.
+pub fn msgpack_body<T: Serialize + ?Sized>(value: &T) -> ApiResult<reqwest::Body> {
+    let encoded = rmp_serde::to_vec_named(value).map_err(ApiForgeError::serialize)?;
+    Ok(encoded.into())
+}
+
+/// Decodes MessagePack bodies with `rmp_serde`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MsgPackDecoder;
+
+impl ResponseDecoder for MsgPackDecoder {
+    fn decode(&self, body: &[u8], _media_type: &MediaType, seed: DecodeSeed<'_>) -> ApiResult<()> {
+        debug!("Parsing response as MessagePack");
+        let mut deserializer = rmp_serde::Deserializer::from_read_ref(body);
+        seed.deserialize(&mut deserializer).map_err(|e| {
+            error!("MessagePack parsing error: {}", e);
+            ApiForgeError::decode(e)
+        })
+    }
+}
@@ -0,0 +1,108 @@
+use crate::error::ApiForgeError;
+use crate::ApiResult;
+use bytes::Bytes;
+use reqwest::multipart::Part;
+use serde::Serialize;
+
+/// Converts a field to a file part of a multipart form.
+///
+/// Used by `#[request(file)]` fields of the `Request` derive. The conversion borrows the field, as
+/// `multipart_form_data` may be called once per attempt when a request is retried.
+pub trait ToPart {
+    /// Creates the part for the field.
+    fn to_part(&self) -> Part;
+
+    /// The file name sent if the field doesn't set one with `file_name`.
+    fn file_name(&self) -> Option<String> {
+        None
+    }
+
+    /// Whether the part can be created more than once, e.g. to retry the request.
+    fn is_replayable(&self) -> bool {
+        true
+    }
+}
+
+impl ToPart for Vec<u8> {
+    fn to_part(&self) -> Part {
+        Part::bytes(self.clone())
+    }
+}
+
+impl ToPart for Bytes {
+    fn to_part(&self) -> Part {
+        Part::bytes(self.to_vec())
+    }
+}
+
+impl ToPart for String {
+    fn to_part(&self) -> Part {
+        Part::text(self.clone())
+    }
+}
+
+/// Streams the file from disk when the request is sent, so it is never fully loaded into memory.
+///
+/// Errors opening or reading the file are reported when the request is sent.
+#[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+impl ToPart for std::path::PathBuf {
+    fn to_part(&self) -> Part {
+        use crate::error::BoxError;
+        use futures_util::{StreamExt, TryStreamExt};
+
+        let path = self.clone();
+        let stream = futures_util::stream::once(tokio::fs::File::open(path))
+            .map_ok(tokio_util::io::ReaderStream::new)
+            .try_flatten()
+            .map_err(BoxError::from)
+            .boxed();
+        let body = reqwest::Body::wrap_stream(stream);
+
+        match std::fs::metadata(self) {
+            Ok(metadata) => Part::stream_with_length(body, metadata.len()),
+            Err(_) => Part::stream(body),
+        }
+    }
+
+    fn file_name(&self) -> Option<String> {
+        self.as_path()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ToPart for crate::StreamBody {
+    fn to_part(&self) -> Part {
+        match self.length() {
+            Some(length) => Part::stream_with_length(self.to_body(), length),
+            None => Part::stream(self.to_body()),
+        }
+    }
+
+    fn is_replayable(&self) -> bool {
+        false
+    }
+}
+
+/// Creates a text part from a field, or `None` if it serializes to `null`.
+///
+/// Strings are sent as is, other values as their JSON representation, e.g. `42` or `true`.
+/// Fails with a `SerializeError` if the value can't be serialized.
+pub fn text_part<T: Serialize + ?Sized>(value: &T) -> ApiResult<Option<Part>> {
+    match serde_json::to_value(value).map_err(ApiForgeError::serialize)? {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::String(text) => Ok(Some(Part::text(text))),
+        value => Ok(Some(Part::text(value.to_string()))),
+    }
+}
+
+/// Creates an `application/json` part from a field.
+///
+/// Fails with a `SerializeError` if the value can't be serialized.
+pub fn json_part<T: Serialize + ?Sized>(value: &T) -> ApiResult<Part> {
+    let json = serde_json::to_string(value).map_err(ApiForgeError::serialize)?;
+    Ok(Part::text(json)
+        .mime_str("application/json")
+        .expect("application/json is a valid mime type"))
+}
@@ -0,0 +1,446 @@
+use crate::auth::{expect_method, AuthProvider};
+use crate::client::shared_http_client;
+use crate::error::ApiForgeError;
+use crate::middleware::Next;
+use crate::runtime::{timeout, Instant};
+use crate::traits::AuthenticationMethod;
+use crate::ApiResult;
+use async_trait::async_trait;
+use futures_util::lock::Mutex;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Grant type of the JWT bearer grant, as defined by RFC 7523.
+pub const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+/// Successful response of an OAuth2 token endpoint, as defined by RFC 6749.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TokenResponse {
+    /// The access token sent with requests.
+    pub access_token: String,
+
+    /// The type of the token. Only `Bearer` tokens are accepted, matched case-insensitively, but
+    /// servers that leave it out are tolerated.
+    #[serde(default)]
+    pub token_type: String,
+
+    /// Seconds until the access token expires. Absent means the server didn't say.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
+
+    /// A token for obtaining new access tokens with the refresh token grant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+
+    /// The scopes granted, if they differ from the requested ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+impl fmt::Debug for TokenResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenResponse")
+            .field("token_type", &self.token_type)
+            .field("expires_in", &self.expires_in)
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error response of an OAuth2 token endpoint.
+#[derive(Debug, Deserialize)]
+struct TokenError {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// How the client ID and secret are sent to the token endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClientAuthentication {
+    /// HTTP Basic authentication, which all token endpoints support.
+    #[default]
+    Basic,
+    /// `client_id` and `client_secret` parameters in the request body.
+    Body,
+}
+
+/// Creates the assertion of a JWT bearer grant.
+type AssertionFn = dyn Fn() -> ApiResult<String> + Send + Sync;
+
+/// The grant used to obtain access tokens.
+#[derive(Clone)]
+enum Grant {
+    ClientCredentials,
+    RefreshToken,
+    JwtBearer(Arc<AssertionFn>),
+}
+
+/// An access token and when it should be replaced.
+struct CachedToken {
+    access_token: String,
+    refresh_at: Option<Instant>,
+}
+
+impl CachedToken {
+    fn is_fresh(&self) -> bool {
+        self.refresh_at.is_none_or(|refresh_at| Instant::now() < refresh_at)
+    }
+}
+
+/// Cached access token and the latest refresh token.
+#[derive(Default)]
+struct TokenState {
+    token: Option<CachedToken>,
+    refresh_token: Option<String>,
+}
+
+/// Sends access tokens obtained from an OAuth2 token endpoint as bearer tokens.
+///
+/// Supports the client credentials, refresh token and JWT bearer (RFC 7523) grants. The access
+/// token is cached and replaced ahead of its expiry, with concurrent requests waiting for the
+/// same token request. If a request is rejected with `401 Unauthorized`, the token is discarded,
+/// unless it was already replaced, and the request is sent once more with a new one.
+///
+/// Token requests of an `ApiClient` go through its middleware and transport, so mock transports
+/// also answer them. Elsewhere they are sent with the shared `reqwest::Client`, or the one set
+/// with [`http_client`](Self::http_client).
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{ApiClient, OAuth2Auth};
+/// use std::time::Duration;
+///
+/// let auth = OAuth2Auth::client_credentials("https://auth.example.com/oauth/token", "my-client", "my-secret")
+///     .scope("read:posts")
+///     .refresh_margin(Duration::from_secs(30));
+///
+/// let client = ApiClient::builder("https://api.example.com")
+///     .auth(auth)
+///     .build();
+/// ```
+pub struct OAuth2Auth {
+    token_url: String,
+    grant: Grant,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    client_authentication: ClientAuthentication,
+    scopes: Vec<String>,
+    refresh_margin: Duration,
+    timeout: Duration,
+    http: Option<reqwest::Client>,
+    state: Mutex<TokenState>,
+}
+
+impl OAuth2Auth {
+    fn new(token_url: impl Into<String>, grant: Grant) -> Self {
+        Self {
+            token_url: token_url.into(),
+            grant,
+            client_id: None,
+            client_secret: None,
+            client_authentication: ClientAuthentication::default(),
+            scopes: Vec::new(),
+            refresh_margin: Duration::from_secs(60),
+            timeout: Duration::from_secs(30),
+            http: None,
+            state: Mutex::new(TokenState::default()),
+        }
+    }
+
+    /// Obtains tokens with the client credentials grant.
+    pub fn client_credentials(
+        token_url: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self::new(token_url, Grant::ClientCredentials)
+            .client_id(client_id)
+            .client_secret(client_secret)
+    }
+
+    /// Obtains tokens with the refresh token grant.
+    ///
+    /// If the server rotates refresh tokens, the new one is used for the next token request, see
+    /// [`latest_refresh_token`](Self::latest_refresh_token). Set a secret with
+    /// [`client_secret`](Self::client_secret) for confidential clients.
+    pub fn refresh_token(
+        token_url: impl Into<String>,
+        client_id: impl Into<String>,
+        refresh_token: impl Into<String>,
+    ) -> Self {
+        let state = TokenState {
+            token: None,
+            refresh_token: Some(refresh_token.into()),
+        };
+        Self {
+            state: Mutex::new(state),
+            ..Self::new(token_url, Grant::RefreshToken).client_id(client_id)
+        }
+    }
+
+    /// Obtains tokens with the JWT bearer grant.
+    ///
+    /// `assertion` creates the signed JWT for each token request, as assertions are short-lived.
+    /// Sign it with a JWT library such as `jsonwebtoken`.
+    pub fn jwt_bearer<F>(token_url: impl Into<String>, assertion: F) -> Self
+    where
+        F: Fn() -> ApiResult<String> + Send + Sync + 'static,
+    {
+        Self::new(token_url, Grant::JwtBearer(Arc::new(assertion)))
+    }
+
+    /// Sets the client ID sent to the token endpoint.
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    /// Sets the client secret sent to the token endpoint.
+    pub fn client_secret(mut self, client_secret: impl Into<String>) -> Self {
+        self.client_secret = Some(client_secret.into());
+        self
+    }
+
+    /// Sets how the client ID and secret are sent. Defaults to HTTP Basic authentication.
+    pub fn client_authentication(mut self, method: ClientAuthentication) -> Self {
+        self.client_authentication = method;
+        self
+    }
+
+    /// Adds a scope to request.
+    pub fn scope(mut self, scope: impl Into<String>) -> Self {
+        self.scopes.push(scope.into());
+        self
+    }
+
+    /// Sets how long before its expiry a token is replaced. Defaults to 60 seconds.
+    ///
+    /// Tokens are replaced after at most half their lifetime, so short-lived tokens are reused.
+    pub fn refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    /// Sets how long a token request may take before it fails. Defaults to 30 seconds.
+    ///
+    /// Other requests wait for the token request, so it is bounded even if the client has no
+    /// timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Uses an existing `reqwest::Client` for token requests instead of the shared one, or the
+    /// transport of the `ApiClient` sending the request.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http = Some(client);
+        self
+    }
+
+    /// Returns a valid access token, requesting a new one if the cached token is missing or
+    /// about to expire.
+    pub async fn access_token(&self) -> ApiResult<String> {
+        self.fetch_access_token(None).await
+    }
+
+    /// Returns a valid access token, requesting a new one through `next` if given.
+    async fn fetch_access_token(&self, next: Option<Next<'_>>) -> ApiResult<String> {
+        let mut state = self.state.lock().await;
+        if let Some(token) = state.token.as_ref().filter(|token| token.is_fresh()) {
+            return Ok(token.access_token.clone());
+        }
+
+        let refresh_token = state.refresh_token.as_deref();
+        let response = timeout(self.timeout, self.request_token(refresh_token, next))
+            .await
+            .ok_or_else(|| {
+                ApiForgeError::auth(format!(
+                    "Token request to {} timed out after {:?}",
+                    self.token_url, self.timeout
+                ))
+            })??;
+        let access_token = response.access_token.clone();
+        state.token = Some(CachedToken {
+            access_token: response.access_token,
+            refresh_at: response.expires_in.map(|expires_in| self.refresh_at(expires_in)),
+        });
+        if response.refresh_token.is_some() {
+            state.refresh_token = response.refresh_token;
+        }
+        Ok(access_token)
+    }
+
+    /// Returns the latest refresh token, for storing tokens the server rotated.
+    pub async fn latest_refresh_token(&self) -> Option<String> {
+        self.state.lock().await.refresh_token.clone()
+    }
+
+    /// Discards the cached access token, so the next request obtains a new one.
+    pub async fn invalidate(&self) {
+        self.state.lock().await.token = None;
+    }
+
+    /// Discards the cached access token if it is the rejected one, so a token obtained by a
+    /// concurrent request in the meantime is kept.
+    async fn invalidate_rejected(&self, rejected: Option<&str>) {
+        let mut state = self.state.lock().await;
+        let current = state.token.as_ref().map(|token| token.access_token.as_str());
+        if rejected.is_none() || rejected == current {
+            state.token = None;
+        } else {
+            debug!("Rejected access token was already replaced");
+        }
+    }
+
+    /// Returns when a token expiring in `expires_in` seconds should be replaced.
+    fn refresh_at(&self, expires_in: u64) -> Instant {
+        let lifetime = Duration::from_secs(expires_in);
+        Instant::now() + lifetime - self.refresh_margin.min(lifetime / 2)
+    }
+
+    /// Requests a new token from the token endpoint, through `next` if given.
+    async fn request_token(&self, refresh_token: Option<&str>, next: Option<Next<'_>>) -> ApiResult<TokenResponse> {
+        let mut params: Vec<(&str, String)> = Vec::new();
+        match &self.grant {
+            Grant::ClientCredentials => params.push(("grant_type", "client_credentials".to_string())),
+            Grant::RefreshToken => {
+                let refresh_token = refresh_token
+                    .ok_or_else(|| ApiForgeError::auth("No refresh token available for the refresh token grant"))?;
+                params.push(("grant_type", "refresh_token".to_string()));
+                params.push(("refresh_token", refresh_token.to_string()));
+            }
+            Grant::JwtBearer(assertion) => {
+                params.push(("grant_type", JWT_BEARER_GRANT_TYPE.to_string()));
+                params.push(("assertion", assertion()?));
+            }
+        }
+        if !self.scopes.is_empty() {
+            params.push(("scope", self.scopes.join(" ")));
+        }
+
+        let http = self.http.as_ref().unwrap_or_else(|| shared_http_client());
+        let mut builder = http.post(&self.token_url);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            builder = builder.timeout(self.timeout);
+        }
+        if let Some(client_id) = &self.client_id {
+            match self.client_authentication {
+                ClientAuthentication::Basic => {
+                    // RFC 6749 §2.3.1: the credentials are form-encoded before Basic encoding
+                    let client_secret = self.client_secret.as_deref().map(form_encode);
+                    builder = builder.basic_auth(form_encode(client_id), client_secret);
+                }
+                ClientAuthentication::Body => {
+                    params.push(("client_id", client_id.clone()));
+                    if let Some(client_secret) = &self.client_secret {
+                        params.push(("client_secret", client_secret.clone()));
+                    }
+                }
+            }
+        }
+
+        info!("Requesting OAuth2 access token from {}...", self.token_url);
+        let request = builder.form(&params).build().map_err(ApiForgeError::auth)?;
+        let response = match (next, &self.http) {
+            (Some(next), None) => next.run(request).await?,
+            _ => http.execute(request).await.map_err(ApiForgeError::auth)?,
+        };
+        let status = response.status();
+        let body = response.bytes().await.map_err(ApiForgeError::auth)?;
+
+        if !status.is_success() {
+            let message = match serde_json::from_slice::<TokenError>(&body) {
+                Ok(TokenError {
+                    error,
+                    error_description: Some(description),
+                }) => format!("{}: {}", error, description),
+                Ok(TokenError { error, .. }) => error,
+                Err(_) => String::from_utf8_lossy(&body).into_owned(),
+            };
+            return Err(ApiForgeError::auth(format!("Token request failed with {}: {}", status, message)));
+        }
+
+        let token: TokenResponse = serde_json::from_slice(&body).map_err(ApiForgeError::auth)?;
+        debug!("Received access token: {:?}", token);
+        if !token.token_type.is_empty() && !token.token_type.eq_ignore_ascii_case("bearer") {
+            return Err(ApiForgeError::auth(format!(
+                "Token endpoint returned a {} token, but only Bearer tokens are supported",
+                token.token_type
+            )));
+        }
+        Ok(token)
+    }
+}
+
+/// Encodes a client ID or secret with `application/x-www-form-urlencoded`.
+fn form_encode(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+impl fmt::Debug for OAuth2Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let grant = match self.grant {
+            Grant::ClientCredentials => "client_credentials",
+            Grant::RefreshToken => "refresh_token",
+            Grant::JwtBearer(_) => JWT_BEARER_GRANT_TYPE,
+        };
+        f.debug_struct("OAuth2Auth")
+            .field("token_url", &self.token_url)
+            .field("grant", &grant)
+            .field("client_id", &self.client_id)
+            .field("client_authentication", &self.client_authentication)
+            .field("scopes", &self.scopes)
+            .field("refresh_margin", &self.refresh_margin)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl AuthProvider for OAuth2Auth {
+    async fn apply(&self, request: &mut reqwest::Request, method: AuthenticationMethod) -> ApiResult<()> {
+        expect_method(AuthenticationMethod::Bearer, method)?;
+        let token = self.access_token().await?;
+        insert_token(request, &token)
+    }
+
+    async fn apply_with(
+        &self,
+        request: &mut reqwest::Request,
+        method: AuthenticationMethod,
+        next: Next<'_>,
+    ) -> ApiResult<()> {
+        expect_method(AuthenticationMethod::Bearer, method)?;
+        let token = self.fetch_access_token(Some(next)).await?;
+        insert_token(request, &token)
+    }
+
+    async fn on_rejected(&self, method: AuthenticationMethod, sent: &HeaderMap, _headers: &HeaderMap) -> ApiResult<bool> {
+        if method != AuthenticationMethod::Bearer {
+            return Ok(false);
+        }
+        debug!("Access token was rejected, requesting a new one");
+        let rejected = sent
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        self.invalidate_rejected(rejected).await;
+        Ok(true)
+    }
+}
+
+/// Sends the access token in the `Authorization: Bearer` header.
+fn insert_token(request: &mut reqwest::Request, token: &str) -> ApiResult<()> {
+    let mut value = HeaderValue::from_str(&format!("Bearer {}", token)).map_err(ApiForgeError::auth)?;
+    value.set_sensitive(true);
+    request.headers_mut().insert(AUTHORIZATION, value);
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use crate::decoder::{utf8_body, MediaType};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::debug;
+
+/// Media type of JSON problem details.
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Problem details of an error response, as defined by RFC 9457 (formerly RFC 7807).
+///
+/// Error responses with the `application/problem+json` media type are always returned as
+/// `ApiForgeError::Problem`. An error type set on the request or client only applies to other
+/// error bodies.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{ApiForgeError, ProblemDetails};
+///
+/// fn describe(err: &ApiForgeError) -> Option<String> {
+///     let problem = err.problem()?;
+///     let balance = problem.extension::<i64>("balance");
+///     Some(format!("{} (type {}, balance {:?})", problem, problem.problem_type(), balance))
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    /// A URI reference identifying the problem type. Absent means `about:blank`.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub type_uri: Option<String>,
+
+    /// A short, human-readable summary of the problem type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// The HTTP status code set by the server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    /// A human-readable explanation specific to this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
+    /// A URI reference identifying this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+
+    /// Extension members specific to the problem type.
+    #[serde(flatten)]
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ProblemDetails {
+    /// Returns the problem type, `about:blank` if the server didn't set one.
+    pub fn problem_type(&self) -> &str {
+        self.type_uri.as_deref().unwrap_or("about:blank")
+    }
+
+    /// Deserializes an extension member, returning `None` if it is absent or of another type.
+    pub fn extension<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let value = self.extensions.get(name)?;
+        T::deserialize(value).ok()
+    }
+
+    /// Parses problem details from an error body if its content type is
+    /// `application/problem+json`.
+    pub(crate) fn from_body(content_type: Option<&str>, body: &[u8]) -> Option<Self> {
+        let media_type = content_type.and_then(MediaType::parse)?;
+        if media_type.essence() != PROBLEM_JSON_CONTENT_TYPE {
+            return None;
+        }
+
+        utf8_body(body, &media_type)
+            .and_then(|body| Ok(serde_json::from_slice(&body)?))
+            .map_err(|e| debug!("Failed to parse problem details, keeping the raw text: {}", e))
+            .ok()
+    }
+}
+
+/// Formats the title and detail, falling back to the problem type.
+impl fmt::Display for ProblemDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.title, &self.detail) {
+            (Some(title), Some(detail)) => write!(f, "{}: {}", title, detail),
+            (Some(message), None) | (None, Some(message)) => f.write_str(message),
+            (None, None) => f.write_str(self.problem_type()),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::decoder::{DecodeSeed, MediaType, ResponseDecoder};
+use crate::error::ApiForgeError;
+use crate::ApiResult;
+use serde::de::value::BytesDeserializer;
+use serde::de::{Deserialize, Deserializer, Error, Visitor};
+use tracing::{debug, error};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// Content type of Protobuf bodies.
+pub const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Creates the request body for `DataTransmissionMethod::Protobuf`.
+pub fn protobuf_body<M: prost::Message>(message: &M) -> reqwest::Body {
+    message.encode_to_vec().into()
+}
+
+/// A response decoded from an `application/x-protobuf` body with prost.
+///
+/// Protobuf messages don't implement `Deserialize`, so use this wrapper as the response type,
+/// e.g. `response_type = "Protobuf<User>"`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use api_forge::{ApiClient, Protobuf, Request};
+/// use serde::Serialize;
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct User {
+///     #[prost(string, tag = "1")]
+///     name: String,
+/// }
+///
+/// #[derive(Serialize, Debug, Request)]
+/// #[request(endpoint = "/users/{id}", path_parameters = ["id"], accept = "application/x-protobuf", response_type = "Protobuf<User>")]
+/// struct GetUser {
+///     id: u64,
+/// }
+///
+/// # async fn run() -> api_forge::ApiResult<()> {
+/// let user = ApiClient::new("https://api.example.com").execute(&GetUser { id: 1 }).await?;
+/// println!("{}", user.name);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Protobuf<M>(pub M);
+
+impl<M> Protobuf<M> {
+    /// Returns the decoded message.
+    pub fn into_inner(self) -> M {
+        self.0
+    }
+}
+
+impl<M> Deref for Protobuf<M> {
+    type Target = M;
+
+    fn deref(&self) -> &M {
+        &self.0
+    }
+}
+
+impl<M> DerefMut for Protobuf<M> {
+    fn deref_mut(&mut self) -> &mut M {
+        &mut self.0
+    }
+}
+
+impl<'de, M: prost::Message + Default> Deserialize<'de> for Protobuf<M> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ProtobufVisitor<M>(PhantomData<M>);
+
+        impl<M: prost::Message + Default> Visitor<'_> for ProtobufVisitor<M> {
+            type Value = Protobuf<M>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an encoded Protobuf message")
+            }
+
+            fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+                M::decode(bytes).map(Protobuf).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(ProtobufVisitor(PhantomData))
+    }
+}
+
+/// Passes Protobuf bodies as raw bytes to the response type, such as `Protobuf<M>`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProtobufDecoder;
+
+impl ResponseDecoder for ProtobufDecoder {
+    fn decode(&self, body: &[u8], _media_type: &MediaType, seed: DecodeSeed<'_>) -> ApiResult<()> {
+        debug!("Parsing response as Protobuf");
+        seed.deserialize(BytesDeserializer::<serde::de::value::Error>::new(body))
+            .map_err(|e| {
+                error!("Protobuf parsing error: {}", e);
+                ApiForgeError::decode(e)
+            })
+    }
+}
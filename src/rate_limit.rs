@@ -0,0 +1,426 @@
+use crate::retry::retry_after;
+use crate::runtime::{sleep, Instant, SystemTime, UNIX_EPOCH};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::debug;
+
+/// Values of `X-RateLimit-Reset` above this are treated as Unix timestamps instead of seconds.
+const UNIX_TIMESTAMP_THRESHOLD: u64 = 1_000_000_000;
+
+/// A token bucket limit: at most `requests` requests per `period`, refilled continuously.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::RateLimit;
+///
+/// const LIMIT: RateLimit = RateLimit::per_second(10);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Maximum number of requests in one period, which is also the burst size.
+    pub requests: u32,
+    /// Length of the period.
+    pub period: Duration,
+}
+
+impl RateLimit {
+    /// Creates a limit of `requests` requests per `period`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` is zero, as no request could ever be sent. Use no limiter instead.
+    pub const fn new(requests: u32, period: Duration) -> Self {
+        assert!(requests > 0, "a rate limit must allow at least one request per period");
+        Self { requests, period }
+    }
+
+    /// Creates a limit of `requests` requests per second.
+    pub const fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    /// Creates a limit of `requests` requests per minute.
+    pub const fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    /// Time needed to refill a single token.
+    fn refill_interval(&self) -> Duration {
+        self.period / self.requests
+    }
+}
+
+/// How a `RateLimiter` groups requests into buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitScope {
+    /// All requests sent by the client share one bucket.
+    Client,
+    /// Every endpoint (`ApiRequest::ENDPOINT`) has its own bucket.
+    Endpoint,
+}
+
+/// Limits reported by the server through rate limit headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerRateLimit {
+    /// Requests left in the current window.
+    pub remaining: u64,
+    /// Time until the window resets.
+    pub reset: Duration,
+}
+
+impl ServerRateLimit {
+    /// Parses the limits reported in the response headers.
+    ///
+    /// Supports `X-RateLimit-Remaining`/`X-RateLimit-Reset`, the `RateLimit-Remaining`/
+    /// `RateLimit-Reset` headers and the structured `RateLimit` header of the IETF draft.
+    /// `X-RateLimit-Reset` may be either seconds or a Unix timestamp.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let number = |name: &str| header(name).and_then(|value| value.trim().parse::<u64>().ok());
+
+        if let (Some(remaining), Some(reset)) = (number("x-ratelimit-remaining"), number("x-ratelimit-reset")) {
+            return Some(Self { remaining, reset: reset_duration(reset) });
+        }
+
+        if let (Some(remaining), Some(reset)) = (number("ratelimit-remaining"), number("ratelimit-reset")) {
+            return Some(Self { remaining, reset: Duration::from_secs(reset) });
+        }
+
+        header("ratelimit").and_then(Self::from_structured)
+    }
+
+    /// Parses the structured `RateLimit` header, e.g. `limit=100, remaining=50, reset=30` or
+    /// `"default";r=50;t=30`.
+    fn from_structured(value: &str) -> Option<Self> {
+        let mut remaining = None;
+        let mut reset = None;
+
+        for param in value.split([',', ';']) {
+            let Some((key, value)) = param.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"').parse::<u64>().ok();
+            match key.trim() {
+                "remaining" | "r" => remaining = value,
+                "reset" | "t" => reset = value,
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            remaining: remaining?,
+            reset: Duration::from_secs(reset?),
+        })
+    }
+}
+
+/// Converts an `X-RateLimit-Reset` value, given as seconds or a Unix timestamp, to a duration.
+fn reset_duration(reset: u64) -> Duration {
+    if reset < UNIX_TIMESTAMP_THRESHOLD {
+        return Duration::from_secs(reset);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Duration::from_secs(reset.saturating_sub(now))
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    server_remaining: Option<u64>,
+    server_reset: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(limit: Option<&RateLimit>) -> Self {
+        Self {
+            tokens: limit.map(|limit| limit.requests as f64).unwrap_or_default(),
+            updated: Instant::now(),
+            server_remaining: None,
+            server_reset: None,
+        }
+    }
+
+    /// Takes a token if one is available, otherwise returns how long to wait for one.
+    fn try_acquire(&mut self, limit: Option<&RateLimit>, now: Instant) -> Result<(), Duration> {
+        // Server reported limits take precedence until their window resets.
+        if let Some(reset) = self.server_reset {
+            if now >= reset {
+                self.server_remaining = None;
+                self.server_reset = None;
+            } else if self.server_remaining == Some(0) {
+                return Err(reset - now);
+            }
+        }
+
+        if let Some(limit) = limit {
+            let refill = now.duration_since(self.updated).as_secs_f64() / limit.refill_interval().as_secs_f64();
+            self.tokens = (self.tokens + refill).min(limit.requests as f64);
+            self.updated = now;
+
+            if self.tokens < 1.0 {
+                return Err(limit.refill_interval().mul_f64(1.0 - self.tokens));
+            }
+            self.tokens -= 1.0;
+        }
+
+        if let Some(remaining) = self.server_remaining.as_mut() {
+            *remaining = remaining.saturating_sub(1);
+        }
+
+        Ok(())
+    }
+}
+
+/// Client-side rate limiter used in the send path of `ApiClient`.
+///
+/// Combines an optional configured token bucket with the limits the server reports in its
+/// responses. Before every request, the limiter waits until both allow it, so requests are
+/// delayed instead of being rejected with `429 Too Many Requests`. A `429` or `503` response with
+/// `Retry-After` pauses the bucket until the given time.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{ApiClient, RateLimit, RateLimiter};
+///
+/// let client = ApiClient::builder("https://api.example.com")
+///     .rate_limiter(RateLimiter::per_endpoint(RateLimit::per_second(5)))
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: Option<RateLimit>,
+    scope: RateLimitScope,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter with one bucket shared by all requests of the client.
+    pub fn new(limit: RateLimit) -> Self {
+        Self::with_scope(Some(limit), RateLimitScope::Client)
+    }
+
+    /// Creates a limiter with one bucket per endpoint.
+    pub fn per_endpoint(limit: RateLimit) -> Self {
+        Self::with_scope(Some(limit), RateLimitScope::Endpoint)
+    }
+
+    /// Creates a limiter that only follows the limits reported by the server.
+    pub fn server_reported(scope: RateLimitScope) -> Self {
+        Self::with_scope(None, scope)
+    }
+
+    /// Creates a limiter with an optional configured limit and the given scope.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the limit allows zero requests per period.
+    pub fn with_scope(limit: Option<RateLimit>, scope: RateLimitScope) -> Self {
+        if let Some(limit) = &limit {
+            assert!(limit.requests > 0, "a rate limit must allow at least one request per period");
+        }
+        Self {
+            limit,
+            scope,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the configured limit, if any.
+    pub fn limit(&self) -> Option<&RateLimit> {
+        self.limit.as_ref()
+    }
+
+    /// Returns how requests are grouped into buckets.
+    pub fn scope(&self) -> RateLimitScope {
+        self.scope
+    }
+
+    fn key<'a>(&self, endpoint: &'a str) -> &'a str {
+        match self.scope {
+            RateLimitScope::Client => "",
+            RateLimitScope::Endpoint => endpoint,
+        }
+    }
+
+    /// Waits until a request to the given endpoint is allowed.
+    pub async fn acquire(&self, endpoint: &str) {
+        let key = self.key(endpoint);
+
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+                let bucket = buckets
+                    .entry(key.to_string())
+                    .or_insert_with(|| Bucket::new(self.limit.as_ref()));
+                match bucket.try_acquire(self.limit.as_ref(), Instant::now()) {
+                    Ok(()) => return,
+                    Err(wait) => wait,
+                }
+            };
+
+            debug!("Rate limit reached for '{}', waiting {:?}", endpoint, wait);
+            sleep(wait).await;
+        }
+    }
+
+    /// Updates the limiter with the rate limit headers of a response to the given endpoint.
+    pub fn update(&self, endpoint: &str, status: StatusCode, headers: &HeaderMap) {
+        let server_limit = if matches!(status, StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE) {
+            retry_after(headers)
+                .map(|reset| ServerRateLimit { remaining: 0, reset })
+                .or_else(|| ServerRateLimit::from_headers(headers))
+        } else {
+            ServerRateLimit::from_headers(headers)
+        };
+
+        let Some(server_limit) = server_limit else {
+            return;
+        };
+
+        debug!("Server rate limit for '{}': {:?}", endpoint, server_limit);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        let bucket = buckets
+            .entry(self.key(endpoint).to_string())
+            .or_insert_with(|| Bucket::new(self.limit.as_ref()));
+        bucket.server_remaining = Some(server_limit.remaining);
+        bucket.server_reset = Some(Instant::now() + server_limit.reset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (HeaderName::from_static(name), HeaderValue::from_str(value).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn bucket_refills_one_token_per_interval() {
+        let limit = RateLimit::new(2, Duration::from_secs(2));
+        let start = Instant::now();
+        let mut bucket = Bucket::new(Some(&limit));
+        bucket.updated = start;
+
+        assert_eq!(bucket.try_acquire(Some(&limit), start), Ok(()));
+        assert_eq!(bucket.try_acquire(Some(&limit), start), Ok(()));
+        assert_eq!(bucket.try_acquire(Some(&limit), start), Err(Duration::from_secs(1)));
+
+        let half = start + Duration::from_millis(500);
+        assert_eq!(bucket.try_acquire(Some(&limit), half), Err(Duration::from_millis(500)));
+
+        let refilled = start + Duration::from_secs(1);
+        assert_eq!(bucket.try_acquire(Some(&limit), refilled), Ok(()));
+        assert!(bucket.try_acquire(Some(&limit), refilled).is_err());
+
+        // An idle bucket refills up to the burst size only.
+        let idle = refilled + Duration::from_secs(60);
+        assert_eq!(bucket.try_acquire(Some(&limit), idle), Ok(()));
+        assert_eq!(bucket.try_acquire(Some(&limit), idle), Ok(()));
+        assert!(bucket.try_acquire(Some(&limit), idle).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one request")]
+    fn zero_requests_are_rejected() {
+        RateLimit::new(0, Duration::from_secs(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one request")]
+    fn zero_requests_are_rejected_by_the_limiter() {
+        let limit = RateLimit {
+            requests: 0,
+            period: Duration::from_secs(1),
+        };
+        RateLimiter::with_scope(Some(limit), RateLimitScope::Client);
+    }
+
+    #[test]
+    fn parses_x_ratelimit_headers() {
+        let limit = ServerRateLimit::from_headers(&headers(&[
+            ("x-ratelimit-remaining", "7"),
+            ("x-ratelimit-reset", "30"),
+        ]));
+        assert_eq!(
+            limit,
+            Some(ServerRateLimit {
+                remaining: 7,
+                reset: Duration::from_secs(30)
+            })
+        );
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let reset = (now + 60).to_string();
+        let limit = ServerRateLimit::from_headers(&headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", &reset),
+        ]))
+        .unwrap();
+        assert_eq!(limit.remaining, 0);
+        assert!(limit.reset > Duration::from_secs(55) && limit.reset <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn parses_ratelimit_headers() {
+        let limit = ServerRateLimit::from_headers(&headers(&[("ratelimit-remaining", "3"), ("ratelimit-reset", "10")]));
+        assert_eq!(
+            limit,
+            Some(ServerRateLimit {
+                remaining: 3,
+                reset: Duration::from_secs(10)
+            })
+        );
+
+        let expected = Some(ServerRateLimit {
+            remaining: 50,
+            reset: Duration::from_secs(30),
+        });
+        let limit = ServerRateLimit::from_headers(&headers(&[("ratelimit", "limit=100, remaining=50, reset=30")]));
+        assert_eq!(limit, expected);
+        let limit = ServerRateLimit::from_headers(&headers(&[("ratelimit", "\"default\";r=50;t=30")]));
+        assert_eq!(limit, expected);
+
+        assert_eq!(ServerRateLimit::from_headers(&headers(&[("ratelimit-remaining", "3")])), None);
+    }
+
+    #[test]
+    fn retry_after_pauses_the_bucket() {
+        let limiter = RateLimiter::server_reported(RateLimitScope::Endpoint);
+        limiter.update("/posts", StatusCode::TOO_MANY_REQUESTS, &headers(&[("retry-after", "5")]));
+
+        let mut buckets = limiter.buckets.lock().unwrap();
+        let wait = buckets.get_mut("/posts").unwrap().try_acquire(None, Instant::now()).unwrap_err();
+        assert!(wait > Duration::from_secs(4) && wait <= Duration::from_secs(5), "{:?}", wait);
+        assert!(!buckets.contains_key("/users"));
+    }
+
+    #[test]
+    fn server_remaining_is_counted_down() {
+        let limiter = RateLimiter::server_reported(RateLimitScope::Client);
+        limiter.update(
+            "/posts",
+            StatusCode::OK,
+            &headers(&[("x-ratelimit-remaining", "1"), ("x-ratelimit-reset", "30")]),
+        );
+
+        let mut buckets = limiter.buckets.lock().unwrap();
+        let bucket = buckets.get_mut("").unwrap();
+        assert_eq!(bucket.try_acquire(None, Instant::now()), Ok(()));
+        assert!(bucket.try_acquire(None, Instant::now()).is_err());
+    }
+}
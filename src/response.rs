@@ -0,0 +1,220 @@
+use crate::decoder::DecoderRegistry;
+use crate::error::{ApiForgeError, ErrorType};
+use crate::problem::ProblemDetails;
+use crate::ApiResult;
+use reqwest::header::{AsHeaderName, HeaderMap};
+use reqwest::{StatusCode, Url};
+use serde::de::value::UnitDeserializer;
+use serde::de::{DeserializeOwned, Deserializer, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{debug, error};
+
+/// A parsed response body together with the response metadata.
+///
+/// Returned by `send_and_parse_with_meta` and `ApiClient::execute_with_meta`, for APIs that
+/// return information such as pagination, `ETag`s, `Location`s or request IDs in headers.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use api_forge::{ApiClient, Request};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Debug, Request)]
+/// #[request(endpoint = "/posts", response_type = "Vec<Post>")]
+/// struct GetPosts;
+///
+/// #[derive(Deserialize, Debug)]
+/// struct Post {
+///     id: i32,
+/// }
+///
+/// # async fn run() -> api_forge::ApiResult<()> {
+/// let client = ApiClient::new("https://jsonplaceholder.typicode.com");
+/// let response = client.execute_with_meta(&GetPosts).await?;
+/// let total = response.header("X-Total-Count").and_then(|count| count.parse::<usize>().ok());
+/// println!("{} of {:?} posts in {:?}", response.body.len(), total, response.elapsed);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ApiResponse<T> {
+    /// The parsed response body.
+    pub body: T,
+    /// The response status.
+    pub status: StatusCode,
+    /// The response headers.
+    pub headers: HeaderMap,
+    /// The final URL of the response, after redirects.
+    pub url: Url,
+    /// The time from sending the request, including retries, until the body was parsed.
+    pub elapsed: Duration,
+}
+
+impl<T> ApiResponse<T> {
+    /// Returns the value of a header, if present and valid text.
+    pub fn header<K: AsHeaderName>(&self, name: K) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    /// Returns the parsed body, dropping the metadata.
+    pub fn into_body(self) -> T {
+        self.body
+    }
+
+    /// Maps the body, keeping the metadata.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ApiResponse<U> {
+        ApiResponse {
+            body: f(self.body),
+            status: self.status,
+            headers: self.headers,
+            url: self.url,
+            elapsed: self.elapsed,
+        }
+    }
+}
+
+/// A response type for requests whose response body is of no interest.
+///
+/// It accepts empty bodies and ignores the content of any other body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct EmptyResponse;
+
+impl<'de> Deserialize<'de> for EmptyResponse {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_ignored_any(IgnoredAny)?;
+        Ok(EmptyResponse)
+    }
+}
+
+/// Decodes a response into the expected type.
+///
+/// This is the parsing logic behind `ApiRequest::from_response`, shared by the async and
+/// blocking clients. It handles error statuses, `204 No Content` and empty bodies, and decodes
+/// other bodies with the decoder registered for the `Content-Type` header.
+///
+/// A body is empty if no bytes were received, whatever the `Content-Length` header says, so
+/// chunked and compressed responses are decoded. Empty bodies, including `204 No Content`, are
+/// decoded as a unit value: they succeed for `()`, `Option<T>` and `EmptyResponse`, and fail with
+/// `EmptyBody` for other types, or for any type if the registry is strict.
+///
+/// # Arguments
+///
+/// * `status` - The response status.
+/// * `headers` - The response headers.
+/// * `body` - The raw response body.
+/// * `decoders` - The decoders used for the body.
+/// * `error_type` - The type error bodies are deserialized into, falling back to the error type
+///   of the registry. `application/problem+json` bodies are returned as `Problem` errors before
+///   the error type is tried, and error bodies that don't parse as either are returned as
+///   `ResponseError` text.
+pub fn decode_response<Res>(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &[u8],
+    decoders: &DecoderRegistry,
+    error_type: Option<ErrorType>,
+) -> ApiResult<Res>
+where
+    Res: DeserializeOwned,
+{
+    let content_type = headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .map(|v| v.to_str().unwrap_or(""));
+
+    // Check if the response is successful
+    if !status.is_success() {
+        // Problem details come first, so permissive error types don't swallow them.
+        let problem = ProblemDetails::from_body(content_type, body).map(|problem| ApiForgeError::Problem {
+            status,
+            problem: Box::new(problem),
+        });
+        let api_error = || {
+            error_type
+                .or(decoders.error_type())
+                .and_then(|error_type| error_type.decode(status, content_type, body, decoders))
+        };
+        return Err(problem.or_else(api_error).unwrap_or_else(|| ApiForgeError::ResponseError {
+            status,
+            message: String::from_utf8_lossy(body).into_owned(),
+        }));
+    }
+
+    if status == StatusCode::NO_CONTENT {
+        debug!("Status is 204 No Content, decoding an empty response.");
+        return decode_empty(status);
+    }
+
+    if body.is_empty() {
+        if decoders.is_strict() {
+            error!("Response body is empty");
+            return Err(ApiForgeError::EmptyBody { status });
+        }
+        debug!("Response is empty, decoding an empty response.");
+        return decode_empty(status);
+    }
+
+    decoders.decode(content_type, body)
+}
+
+/// Decodes an empty body into types that accept a unit value, such as `()` or `Option<T>`.
+fn decode_empty<Res: DeserializeOwned>(status: StatusCode) -> ApiResult<Res> {
+    Res::deserialize(UnitDeserializer::<serde::de::value::Error>::new()).map_err(|e| {
+        error!("Response body is empty, but the response type requires content: {}", e);
+        ApiForgeError::EmptyBody { status }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, CONTENT_TYPE};
+
+    /// An error type every JSON object parses into.
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct PermissiveError {
+        message: Option<String>,
+    }
+
+    fn decode_error(content_type: &str, body: &str) -> ApiForgeError {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str(content_type).unwrap());
+        decode_response::<()>(
+            StatusCode::BAD_REQUEST,
+            &headers,
+            body.as_bytes(),
+            &DecoderRegistry::default(),
+            Some(ErrorType::of::<PermissiveError>()),
+        )
+        .unwrap_err()
+    }
+
+    #[test]
+    fn problem_details_take_precedence_over_the_error_type() {
+        let error = decode_error(
+            "application/problem+json",
+            r#"{"type":"https://example.com/out-of-credit","title":"Out of credit"}"#,
+        );
+        let problem = error.problem().expect("problem details");
+        assert_eq!(problem.problem_type(), "https://example.com/out-of-credit");
+        assert_eq!(problem.title.as_deref(), Some("Out of credit"));
+    }
+
+    #[test]
+    fn other_error_bodies_use_the_error_type() {
+        let error = decode_error("application/json", r#"{"message":"Invalid name"}"#);
+        assert!(matches!(error, ApiForgeError::Api { status: StatusCode::BAD_REQUEST, .. }), "{:?}", error);
+
+        let error = decode_error("application/problem+json", "[1, 2]");
+        assert!(matches!(error, ApiForgeError::ResponseError { .. }), "{:?}", error);
+
+        let error = decode_error("text/plain", "Bad request");
+        assert!(
+            matches!(&error, ApiForgeError::ResponseError { message, .. } if message == "Bad request"),
+            "{:?}",
+            error
+        );
+    }
+}
@@ -0,0 +1,376 @@
+use crate::runtime::{sleep, SystemTime, UNIX_EPOCH};
+use crate::{ApiForgeError, ApiResult};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use std::future::Future;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Status codes retried by the default `RetryPolicy`.
+pub const DEFAULT_RETRYABLE_STATUSES: &[u16] = &[408, 429, 500, 502, 503, 504];
+
+/// Policy describing if and how failed requests are retried.
+///
+/// Delays grow exponentially from `base_delay` and are capped at `max_delay`. When a `429 Too Many
+/// Requests` or `503 Service Unavailable` response carries a `Retry-After` header, that delay is
+/// used instead. If the server asks for a longer wait than `max_delay`, the response is returned
+/// without retrying.
+///
+/// Only idempotent methods (`GET`, `HEAD`, `OPTIONS`, `TRACE`, `PUT` and `DELETE`) are retried
+/// unless `retry_non_idempotent` is enabled.
+///
+/// All constructors and setters are `const`, so a policy can be declared as a constant and used
+/// with `#[request(retry = MY_POLICY)]`.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::RetryPolicy;
+/// use std::time::Duration;
+///
+/// const POLICY: RetryPolicy = RetryPolicy::new()
+///     .with_max_attempts(5)
+///     .with_base_delay(Duration::from_millis(250))
+///     .with_retryable_statuses(&[429, 503]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry. Doubled for every following attempt.
+    pub base_delay: Duration,
+    /// Upper bound for a single delay.
+    pub max_delay: Duration,
+    /// Whether to randomize delays to avoid many clients retrying in lockstep.
+    pub jitter: bool,
+    /// Response status codes that are retried.
+    pub retryable_statuses: &'static [u16],
+    /// Whether connection errors are retried.
+    pub retry_connect_errors: bool,
+    /// Whether timeouts are retried.
+    pub retry_timeouts: bool,
+    /// Whether non-idempotent methods such as `POST` and `PATCH` are retried.
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryPolicy {
+    /// Creates the default policy: 3 attempts, 100ms base delay, 30s max delay, with jitter.
+    pub const fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retryable_statuses: DEFAULT_RETRYABLE_STATUSES,
+            retry_connect_errors: true,
+            retry_timeouts: true,
+            retry_non_idempotent: false,
+        }
+    }
+
+    /// Creates a policy that never retries.
+    ///
+    /// Useful to opt a single request out of a client-wide policy.
+    pub const fn none() -> Self {
+        Self::new().with_max_attempts(1)
+    }
+
+    /// Sets the total number of attempts, including the first one.
+    pub const fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub const fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the upper bound for a single delay.
+    pub const fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Enables or disables jitter.
+    pub const fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Sets the response status codes that are retried.
+    pub const fn with_retryable_statuses(mut self, statuses: &'static [u16]) -> Self {
+        self.retryable_statuses = statuses;
+        self
+    }
+
+    /// Sets whether connection errors are retried.
+    pub const fn with_retry_connect_errors(mut self, retry: bool) -> Self {
+        self.retry_connect_errors = retry;
+        self
+    }
+
+    /// Sets whether timeouts are retried.
+    pub const fn with_retry_timeouts(mut self, retry: bool) -> Self {
+        self.retry_timeouts = retry;
+        self
+    }
+
+    /// Sets whether non-idempotent methods are retried.
+    pub const fn with_retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// Returns true if requests with the given method may be retried.
+    pub fn is_retryable_method(&self, method: &Method) -> bool {
+        self.retry_non_idempotent
+            || matches!(
+                *method,
+                Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+            )
+    }
+
+    /// Returns true if a response with the given status should be retried.
+    pub fn is_retryable_status(&self, status: StatusCode) -> bool {
+        self.retryable_statuses.contains(&status.as_u16())
+    }
+
+    /// Returns true if the given transport error should be retried.
+    pub fn is_retryable_error(&self, err: &reqwest::Error) -> bool {
+        (self.retry_connect_errors && is_connect_error(err)) || (self.retry_timeouts && err.is_timeout())
+    }
+
+    /// Returns the exponential backoff delay before the given retry (1 for the first retry).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+
+        if self.jitter {
+            // Equal jitter: keep half of the delay and randomize the other half.
+            let half = delay / 2;
+            half + half.mul_f64(random_fraction())
+        } else {
+            delay
+        }
+    }
+
+    /// Returns the delay before retrying after the given attempt, or `None` if it should not be retried.
+    ///
+    /// `outcome` is either the status and headers of the response or the error of the attempt.
+    pub(crate) fn retry_delay(
+        &self,
+        attempt: u32,
+        outcome: Result<(StatusCode, &HeaderMap), &ApiForgeError>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        match outcome {
+            Ok((status, headers)) => {
+                if !self.is_retryable_status(status) {
+                    return None;
+                }
+
+                if matches!(status, StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE) {
+                    if let Some(retry_after) = retry_after(headers) {
+                        if retry_after > self.max_delay {
+                            debug!("Retry-After of {:?} exceeds the maximum delay, not retrying", retry_after);
+                            return None;
+                        }
+                        return Some(retry_after);
+                    }
+                }
+
+                Some(self.backoff(attempt))
+            }
+            Err(ApiForgeError::ReqwestError(err)) if self.is_retryable_error(err) => Some(self.backoff(attempt)),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Returns a random number in `[0, 1]`, or `1` if the system has no randomness available.
+fn random_fraction() -> f64 {
+    let mut bytes = [0u8; 8];
+    match getrandom::getrandom(&mut bytes) {
+        Ok(()) => u64::from_le_bytes(bytes) as f64 / u64::MAX as f64,
+        Err(err) => {
+            debug!("No randomness available for jitter: {}", err);
+            1.0
+        }
+    }
+}
+
+/// Returns true if the error happened while connecting.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_connect_error(err: &reqwest::Error) -> bool {
+    err.is_connect()
+}
+
+/// Returns true if the error happened while connecting.
+///
+/// The fetch API doesn't distinguish connection errors, so any failure to send counts as one.
+#[cfg(target_arch = "wasm32")]
+fn is_connect_error(err: &reqwest::Error) -> bool {
+    err.is_request()
+}
+
+/// Parses a `Retry-After` header given either as delay-seconds or as an HTTP-date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value)
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    Some(date.saturating_sub(now))
+}
+
+/// Sends a request, resending it according to the retry policy.
+///
+/// `send` performs a single attempt and is called again for every retry. Without a policy, or
+/// when the method is not retryable, the request is sent once.
+pub(crate) async fn send_with_retry<F, Fut>(
+    policy: Option<&RetryPolicy>,
+    method: &Method,
+    mut send: F,
+) -> ApiResult<reqwest::Response>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ApiResult<reqwest::Response>>,
+{
+    let policy = match policy {
+        Some(policy) if policy.is_retryable_method(method) => policy,
+        _ => return send().await,
+    };
+
+    let mut attempt = 1;
+    loop {
+        let result = send().await;
+
+        let outcome = result.as_ref().map(|response| (response.status(), response.headers()));
+        let Some(delay) = policy.retry_delay(attempt, outcome) else {
+            return result;
+        };
+
+        match &result {
+            Ok(response) => warn!(
+                "Attempt {}/{} failed with status {}, retrying in {:?}",
+                attempt, policy.max_attempts, response.status(), delay
+            ),
+            Err(err) => warn!(
+                "Attempt {}/{} failed: {}, retrying in {:?}",
+                attempt, policy.max_attempts, err, delay
+            ),
+        }
+
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max_delay() {
+        let policy = RetryPolicy::new()
+            .with_jitter(false)
+            .with_base_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_millis(500));
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_stays_within_half_and_full_delay() {
+        let policy = RetryPolicy::new().with_base_delay(Duration::from_millis(400));
+
+        for _ in 0..1000 {
+            let delay = policy.backoff(2);
+            assert!(delay >= Duration::from_millis(400), "{:?} is below half the delay", delay);
+            assert!(delay <= Duration::from_millis(800), "{:?} exceeds the delay", delay);
+        }
+    }
+
+    #[test]
+    fn retry_after_parses_delay_seconds() {
+        assert_eq!(retry_after(&headers("120")), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after(&headers(" 0 ")), Some(Duration::ZERO));
+        assert_eq!(retry_after(&headers("soon")), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn retry_after_parses_http_date() {
+        let date = httpdate::fmt_http_date(std::time::SystemTime::now() + Duration::from_secs(120));
+        let delay = retry_after(&headers(&date)).unwrap();
+        assert!(delay > Duration::from_secs(115) && delay <= Duration::from_secs(120), "{:?}", delay);
+
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn only_idempotent_methods_are_retried() {
+        let policy = RetryPolicy::new();
+        for method in [Method::GET, Method::HEAD, Method::OPTIONS, Method::TRACE, Method::PUT, Method::DELETE] {
+            assert!(policy.is_retryable_method(&method), "{} should be retried", method);
+        }
+        for method in [Method::POST, Method::PATCH, Method::CONNECT] {
+            assert!(!policy.is_retryable_method(&method), "{} should not be retried", method);
+        }
+
+        let policy = policy.with_retry_non_idempotent(true);
+        assert!(policy.is_retryable_method(&Method::POST));
+        assert!(policy.is_retryable_method(&Method::PATCH));
+    }
+
+    #[test]
+    fn retry_delay_prefers_retry_after() {
+        let policy = RetryPolicy::new().with_jitter(false).with_max_delay(Duration::from_secs(10));
+
+        let delay = policy.retry_delay(1, Ok((StatusCode::TOO_MANY_REQUESTS, &headers("3"))));
+        assert_eq!(delay, Some(Duration::from_secs(3)));
+
+        let delay = policy.retry_delay(1, Ok((StatusCode::SERVICE_UNAVAILABLE, &headers("60"))));
+        assert_eq!(delay, None, "waits longer than max_delay are not retried");
+
+        let delay = policy.retry_delay(1, Ok((StatusCode::BAD_GATEWAY, &headers("3"))));
+        assert_eq!(delay, Some(policy.base_delay), "Retry-After only applies to 429 and 503");
+
+        let delay = policy.retry_delay(1, Ok((StatusCode::NOT_FOUND, &HeaderMap::new())));
+        assert_eq!(delay, None);
+
+        let delay = policy.retry_delay(3, Ok((StatusCode::BAD_GATEWAY, &HeaderMap::new())));
+        assert_eq!(delay, None, "the last attempt is not retried");
+    }
+}
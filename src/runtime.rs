@@ -0,0 +1,93 @@
+use futures_util::future::{select, Either};
+use std::future::Future;
+use std::pin::pin;
+use std::time::Duration;
+
+pub(crate) use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+compile_error!("api-forge requires the `wasm` feature when targeting wasm32");
+
+/// Waits for the given duration using the browser's timers.
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await;
+}
+
+/// Waits for the given duration without blocking the async runtime.
+#[cfg(all(not(target_arch = "wasm32"), feature = "native"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Waits for the given duration without an async runtime's timer.
+///
+/// Without the `native` feature there is no timer available, so a helper thread sleeps and wakes
+/// the task, leaving the executor free in the meantime.
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "native")))]
+pub(crate) async fn sleep(duration: Duration) {
+    thread_timer::Sleep::new(duration).await;
+}
+
+/// Runs the future, returning `None` if it doesn't complete within the duration.
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    match select(pin!(future), pin!(sleep(duration))).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "native")))]
+mod thread_timer {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use std::time::Duration;
+
+    /// Whether the timer fired, and the waker of the task waiting for it.
+    type State = Arc<Mutex<(bool, Waker)>>;
+
+    /// Future completing once a helper thread has slept for the duration.
+    pub(crate) struct Sleep {
+        duration: Duration,
+        state: Option<State>,
+    }
+
+    impl Sleep {
+        pub(crate) fn new(duration: Duration) -> Self {
+            Self { duration, state: None }
+        }
+    }
+
+    impl Future for Sleep {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.duration.is_zero() {
+                return Poll::Ready(());
+            }
+
+            if let Some(state) = &self.state {
+                let mut state = state.lock().unwrap_or_else(|err| err.into_inner());
+                if state.0 {
+                    return Poll::Ready(());
+                }
+                state.1.clone_from(cx.waker());
+                return Poll::Pending;
+            }
+
+            let state: State = Arc::new(Mutex::new((false, cx.waker().clone())));
+            let timer = Arc::clone(&state);
+            let duration = self.duration;
+            std::thread::spawn(move || {
+                std::thread::sleep(duration);
+                let mut state = timer.lock().unwrap_or_else(|err| err.into_inner());
+                state.0 = true;
+                state.1.wake_by_ref();
+            });
+            self.state = Some(state);
+            Poll::Pending
+        }
+    }
+}
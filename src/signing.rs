@@ -0,0 +1,731 @@
+use crate::auth::{hex_encode, sensitive_value};
+use crate::error::ApiForgeError;
+use crate::runtime::{SystemTime, UNIX_EPOCH};
+use crate::ApiResult;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION, HOST};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use tracing::debug;
+
+/// Payload hash of SigV4 requests whose body isn't signed.
+pub const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Signs outgoing requests, e.g. with an HMAC over their contents.
+///
+/// Signers are set on a client with `ApiClientBuilder::signer`, or the blocking client's builder.
+/// They run on the fully built request, after the `AuthProvider` and before any middleware, once
+/// per attempt, so each retry gets a fresh signature. Middleware that changes the request
+/// invalidates the signature.
+///
+/// Only the clients run signers: requests sent with the `send_request` and `send_and_parse`
+/// methods of `ApiRequest`, or their blocking variants, are sent unsigned.
+///
+/// On `wasm32`, the returned futures are not `Send`, so implementations use
+/// `#[async_trait(?Send)]` there.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait RequestSigner: Send + Sync + 'static {
+    /// Signs the request, usually by adding headers.
+    async fn sign(&self, request: &mut reqwest::Request) -> ApiResult<()>;
+}
+
+/// Runs the signer on the request, if any.
+pub(crate) async fn sign(request: &mut reqwest::Request, signer: Option<&dyn RequestSigner>) -> ApiResult<()> {
+    match signer {
+        Some(signer) => signer.sign(request).await,
+        None => Ok(()),
+    }
+}
+
+/// Computes the HMAC-SHA256 of the data.
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Returns the hex SHA-256 of the request body, `None` if it is a stream.
+fn body_hash(request: &reqwest::Request) -> Option<String> {
+    let body = match request.body() {
+        Some(body) => body.as_bytes()?,
+        None => &[],
+    };
+    Some(hex_encode(&Sha256::digest(body)))
+}
+
+/// The UTC date and time of a timestamp.
+struct UtcTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u64,
+    minute: u64,
+    second: u64,
+}
+
+impl UtcTime {
+    fn new(time: SystemTime) -> Self {
+        let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let (days, rest) = (secs / 86_400, secs % 86_400);
+
+        // Converts days since the epoch to a civil date, see
+        // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days as i64 + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: rest / 3_600,
+            minute: rest % 3_600 / 60,
+            second: rest % 60,
+        }
+    }
+
+    /// Formats the date, e.g. `20150830`.
+    fn date(&self) -> String {
+        format!("{:04}{:02}{:02}", self.year, self.month, self.day)
+    }
+
+    /// Formats the date and time in the ISO 8601 basic format, e.g. `20150830T123600Z`.
+    fn basic(&self) -> String {
+        format!("{}T{:02}{:02}{:02}Z", self.date(), self.hour, self.minute, self.second)
+    }
+
+    /// Formats the date and time as RFC 3339, e.g. `2015-08-30T12:36:00Z`.
+    fn rfc3339(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986.
+fn uri_encode(value: &[u8]) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for &byte in value {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            byte => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Decodes the percent-encoded bytes of a URL path segment.
+fn percent_decode(value: &str) -> Vec<u8> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| value.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    decoded
+}
+
+/// Signs requests with AWS Signature Version 4, e.g. for S3-compatible object stores.
+///
+/// The path and query are rewritten to their canonical encoding, so the server sees the URL that
+/// was signed. Streaming bodies are sent with an `UNSIGNED-PAYLOAD` hash.
+///
+/// The signature is sent in the `Authorization` header, replacing any credentials the client's
+/// `AuthProvider` set, so requests signed with SigV4 should use `AuthenticationMethod::None`.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{ApiClient, SigV4Signer};
+///
+/// let signer = SigV4Signer::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "us-east-1", "s3");
+///
+/// let client = ApiClient::builder("https://my-bucket.s3.us-east-1.amazonaws.com")
+///     .signer(signer)
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct SigV4Signer {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    region: String,
+    service: String,
+    unsigned_payload: bool,
+}
+
+impl SigV4Signer {
+    /// Creates a signer for the service in the region, e.g. `s3` in `us-east-1`.
+    pub fn new(
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+        region: impl Into<String>,
+        service: impl Into<String>,
+    ) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: None,
+            region: region.into(),
+            service: service.into(),
+            unsigned_payload: false,
+        }
+    }
+
+    /// Sends the session token of temporary credentials in `X-Amz-Security-Token`.
+    pub fn session_token(mut self, token: impl Into<String>) -> Self {
+        self.session_token = Some(token.into());
+        self
+    }
+
+    /// Sends all bodies with an `UNSIGNED-PAYLOAD` hash instead of hashing them.
+    pub fn unsigned_payload(mut self, unsigned: bool) -> Self {
+        self.unsigned_payload = unsigned;
+        self
+    }
+
+    /// Signs the request as of the given time.
+    fn sign_at(&self, request: &mut reqwest::Request, time: SystemTime) -> ApiResult<()> {
+        let time = UtcTime::new(time);
+        let (date, timestamp) = (time.date(), time.basic());
+        let is_s3 = self.service == "s3";
+
+        let payload_hash = match body_hash(request) {
+            Some(hash) if !self.unsigned_payload => hash,
+            _ => UNSIGNED_PAYLOAD.to_string(),
+        };
+
+        // S3 paths are encoded once, other services encode the encoded path again.
+        let url = request.url_mut();
+        let path = url
+            .path()
+            .split('/')
+            .map(|segment| uri_encode(&percent_decode(segment)))
+            .collect::<Vec<_>>()
+            .join("/");
+        let canonical_uri = if is_s3 {
+            path.clone()
+        } else {
+            path.split('/')
+                .map(|segment| uri_encode(segment.as_bytes()))
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+        let mut query = url
+            .query_pairs()
+            .map(|(name, value)| (uri_encode(name.as_bytes()), uri_encode(value.as_bytes())))
+            .collect::<Vec<_>>();
+        query.sort();
+        let canonical_query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&");
+        url.set_path(&path);
+        url.set_query(Some(canonical_query.as_str()).filter(|query| !query.is_empty()));
+
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(ApiForgeError::auth("Can't sign a request without a host")),
+        };
+        let headers = request.headers_mut();
+        headers.insert(HOST, HeaderValue::from_str(&host).map_err(ApiForgeError::auth)?);
+        headers.insert("x-amz-date", HeaderValue::from_str(&timestamp).map_err(ApiForgeError::auth)?);
+        if is_s3 || self.unsigned_payload {
+            headers.insert("x-amz-content-sha256", HeaderValue::from_str(&payload_hash).map_err(ApiForgeError::auth)?);
+        }
+        if let Some(token) = &self.session_token {
+            headers.insert("x-amz-security-token", sensitive_value(token)?);
+        }
+
+        // Headers that are commonly changed on the way to the server aren't signed.
+        let mut canonical_headers = BTreeMap::<&str, Vec<String>>::new();
+        for (name, value) in headers.iter() {
+            if matches!(name.as_str(), "authorization" | "user-agent" | "expect" | "x-amzn-trace-id") {
+                continue;
+            }
+            let value = String::from_utf8_lossy(value.as_bytes());
+            canonical_headers
+                .entry(name.as_str())
+                .or_default()
+                .push(value.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        let signed_headers = canonical_headers.keys().copied().collect::<Vec<_>>().join(";");
+        let canonical_headers: String = canonical_headers
+            .iter()
+            .map(|(name, values)| format!("{}:{}\n", name, values.join(",")))
+            .collect();
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            request.method(),
+            canonical_uri,
+            canonical_query,
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+        debug!("SigV4 canonical request:\n{}", canonical_request);
+
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            hex_encode(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let mut key = hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date.as_bytes());
+        for part in [self.region.as_str(), self.service.as_str(), "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes());
+        }
+        let signature = hex_encode(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        );
+        request.headers_mut().insert(AUTHORIZATION, sensitive_value(&authorization)?);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for SigV4Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigV4Signer")
+            .field("access_key_id", &self.access_key_id)
+            .field("region", &self.region)
+            .field("service", &self.service)
+            .field("unsigned_payload", &self.unsigned_payload)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl RequestSigner for SigV4Signer {
+    async fn sign(&self, request: &mut reqwest::Request) -> ApiResult<()> {
+        self.sign_at(request, SystemTime::now())
+    }
+}
+
+/// A part of the request covered by an `HmacSigner` signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignedComponent {
+    /// The request method, e.g. `POST`.
+    Method,
+    /// The URL path, e.g. `/v1/payments`.
+    Path,
+    /// The URL path and query, e.g. `/v1/payments?limit=10`.
+    PathAndQuery,
+    /// The timestamp, as sent in the timestamp header.
+    Timestamp,
+    /// The hex SHA-256 of the body, which is the hash of an empty body if there is none.
+    BodyHash,
+    /// The value of a request header, empty if it is absent.
+    Header(HeaderName),
+}
+
+/// How the `HmacSigner` timestamp is formatted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// Seconds since the Unix epoch, e.g. `1440938160`.
+    #[default]
+    UnixSeconds,
+    /// Milliseconds since the Unix epoch, e.g. `1440938160000`.
+    UnixMillis,
+    /// RFC 3339 in UTC, e.g. `2015-08-30T12:36:00Z`.
+    Rfc3339,
+}
+
+/// How the `HmacSigner` signature is encoded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignatureEncoding {
+    /// Lowercase hex.
+    #[default]
+    Hex,
+    /// Standard base64 with padding.
+    Base64,
+}
+
+/// Signs requests with an HMAC-SHA256 over configurable parts of the request, as required by
+/// many payment and webhook APIs.
+///
+/// By default, the method, path, timestamp and body hash are joined with newlines, and the hex
+/// signature and Unix timestamp are sent in `X-Signature` and `X-Timestamp`. Requests with a
+/// streaming body can't be signed if the body hash is covered.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{ApiClient, HmacSigner, SignatureEncoding, SignedComponent};
+/// use reqwest::header::HeaderName;
+///
+/// let signer = HmacSigner::new("my-secret")
+///     .key_id(HeaderName::from_static("x-key-id"), "key-1")
+///     .components([
+///         SignedComponent::Timestamp,
+///         SignedComponent::Method,
+///         SignedComponent::PathAndQuery,
+///         SignedComponent::BodyHash,
+///     ])
+///     .separator("|")
+///     .encoding(SignatureEncoding::Base64);
+///
+/// let client = ApiClient::builder("https://payments.example.com")
+///     .signer(signer)
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct HmacSigner {
+    key: Vec<u8>,
+    key_id: Option<(HeaderName, String)>,
+    components: Vec<SignedComponent>,
+    separator: String,
+    signature_header: HeaderName,
+    signature_prefix: String,
+    encoding: SignatureEncoding,
+    timestamp_header: Option<HeaderName>,
+    timestamp_format: TimestampFormat,
+}
+
+impl HmacSigner {
+    /// Creates a signer with the secret key and the default configuration.
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: key.into(),
+            key_id: None,
+            components: vec![
+                SignedComponent::Method,
+                SignedComponent::Path,
+                SignedComponent::Timestamp,
+                SignedComponent::BodyHash,
+            ],
+            separator: "\n".to_string(),
+            signature_header: HeaderName::from_static("x-signature"),
+            signature_prefix: String::new(),
+            encoding: SignatureEncoding::default(),
+            timestamp_header: Some(HeaderName::from_static("x-timestamp")),
+            timestamp_format: TimestampFormat::default(),
+        }
+    }
+
+    /// Sends the ID of the key in the header.
+    pub fn key_id(mut self, header: HeaderName, id: impl Into<String>) -> Self {
+        self.key_id = Some((header, id.into()));
+        self
+    }
+
+    /// Sets the parts of the request covered by the signature, in order.
+    pub fn components(mut self, components: impl IntoIterator<Item = SignedComponent>) -> Self {
+        self.components = components.into_iter().collect();
+        self
+    }
+
+    /// Sets the separator the components are joined with. Defaults to a newline.
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Sets the header the signature is sent in. Defaults to `X-Signature`.
+    pub fn signature_header(mut self, header: HeaderName) -> Self {
+        self.signature_header = header;
+        self
+    }
+
+    /// Sets a prefix of the signature header value, e.g. `sha256=`.
+    pub fn signature_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.signature_prefix = prefix.into();
+        self
+    }
+
+    /// Sets how the signature is encoded. Defaults to hex.
+    pub fn encoding(mut self, encoding: SignatureEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets the header the timestamp is sent in, or `None` to only sign it. Defaults to
+    /// `X-Timestamp`.
+    pub fn timestamp_header(mut self, header: Option<HeaderName>) -> Self {
+        self.timestamp_header = header;
+        self
+    }
+
+    /// Sets how the timestamp is formatted. Defaults to Unix seconds.
+    pub fn timestamp_format(mut self, format: TimestampFormat) -> Self {
+        self.timestamp_format = format;
+        self
+    }
+
+    /// Signs the request as of the given time.
+    fn sign_at(&self, request: &mut reqwest::Request, time: SystemTime) -> ApiResult<()> {
+        let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let timestamp = match self.timestamp_format {
+            TimestampFormat::UnixSeconds => elapsed.as_secs().to_string(),
+            TimestampFormat::UnixMillis => elapsed.as_millis().to_string(),
+            TimestampFormat::Rfc3339 => UtcTime::new(time).rfc3339(),
+        };
+
+        let mut parts = Vec::with_capacity(self.components.len());
+        for component in &self.components {
+            let url = request.url();
+            let part = match component {
+                SignedComponent::Method => request.method().to_string(),
+                SignedComponent::Path => url.path().to_string(),
+                SignedComponent::PathAndQuery => match url.query() {
+                    Some(query) => format!("{}?{}", url.path(), query),
+                    None => url.path().to_string(),
+                },
+                SignedComponent::Timestamp => timestamp.clone(),
+                SignedComponent::BodyHash => body_hash(request)
+                    .ok_or_else(|| ApiForgeError::auth("Can't hash a streaming body for the request signature"))?,
+                SignedComponent::Header(name) => request
+                    .headers()
+                    .get(name)
+                    .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+                    .unwrap_or_default(),
+            };
+            parts.push(part);
+        }
+
+        let mac = hmac_sha256(&self.key, parts.join(&self.separator).as_bytes());
+        let signature = match self.encoding {
+            SignatureEncoding::Hex => hex_encode(&mac),
+            SignatureEncoding::Base64 => STANDARD.encode(mac),
+        };
+
+        let headers = request.headers_mut();
+        if let Some(header) = &self.timestamp_header {
+            headers.insert(header.clone(), HeaderValue::from_str(&timestamp).map_err(ApiForgeError::auth)?);
+        }
+        if let Some((header, id)) = &self.key_id {
+            headers.insert(header.clone(), HeaderValue::from_str(id).map_err(ApiForgeError::auth)?);
+        }
+        let signature = format!("{}{}", self.signature_prefix, signature);
+        headers.insert(self.signature_header.clone(), sensitive_value(&signature)?);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for HmacSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacSigner")
+            .field("key_id", &self.key_id)
+            .field("components", &self.components)
+            .field("separator", &self.separator)
+            .field("signature_header", &self.signature_header)
+            .field("encoding", &self.encoding)
+            .field("timestamp_header", &self.timestamp_header)
+            .field("timestamp_format", &self.timestamp_format)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl RequestSigner for HmacSigner {
+    async fn sign(&self, request: &mut reqwest::Request) -> ApiResult<()> {
+        self.sign_at(request, SystemTime::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::CONTENT_TYPE;
+    use reqwest::Method;
+    use std::time::Duration;
+
+    /// 2015-08-30T12:36:00Z, the time of the AWS SigV4 test suite.
+    fn test_suite_time() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_440_938_160)
+    }
+
+    fn request(method: Method, url: &str) -> reqwest::Request {
+        reqwest::Request::new(method, url.parse().unwrap())
+    }
+
+    /// Signs the request like the AWS SigV4 test suite and returns its `Authorization` header.
+    fn sigv4_authorization(mut request: reqwest::Request) -> String {
+        let signer = SigV4Signer::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "us-east-1", "service");
+        signer.sign_at(&mut request, test_suite_time()).unwrap();
+        assert_eq!(request.headers()["x-amz-date"], "20150830T123600Z");
+        request.headers()[AUTHORIZATION].to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn sigv4_get_vanilla() {
+        let authorization = sigv4_authorization(request(Method::GET, "https://example.amazonaws.com/"));
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn sigv4_get_vanilla_query_order_key() {
+        let request = request(Method::GET, "https://example.amazonaws.com/?Param2=value2&Param1=value1");
+        let authorization = sigv4_authorization(request);
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        );
+    }
+
+    #[test]
+    fn sigv4_post_x_www_form_urlencoded() {
+        let mut request = request(Method::POST, "https://example.amazonaws.com/");
+        request
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/x-www-form-urlencoded"));
+        *request.body_mut() = Some("Param1=value1".into());
+        let authorization = sigv4_authorization(request);
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"
+        );
+    }
+
+    #[test]
+    fn sigv4_replaces_the_authorization_header() {
+        let mut request = request(Method::GET, "https://example.amazonaws.com/");
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, HeaderValue::from_static("Bearer token"));
+        let authorization = sigv4_authorization(request);
+        assert!(authorization.ends_with("5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"));
+    }
+
+    #[test]
+    fn hmac_sha256_matches_rfc_4231() {
+        let long_key = [0xaa; 131];
+        let cases: [(&[u8], &[u8], &str); 6] = [
+            (&[0x0b; 20], b"Hi There", "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"),
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (&[0xaa; 20], &[0xdd; 50], "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe"),
+            (
+                &[
+                    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+                ],
+                &[0xcd; 50],
+                "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+            ),
+            (
+                &long_key,
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+            (
+                &long_key,
+                b"This is a test using a larger than block-size key and a larger than block-size data. \
+                  The key needs to be hashed before being used by the HMAC algorithm.",
+                "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+            ),
+        ];
+        for (key, data, expected) in cases {
+            assert_eq!(hex_encode(&hmac_sha256(key, data)), expected);
+        }
+    }
+
+    #[test]
+    fn hmac_signer_signs_a_header_like_rfc_4231() {
+        let header = HeaderName::from_static("x-message");
+        let signer = HmacSigner::new("Jefe")
+            .components([SignedComponent::Header(header.clone())])
+            .timestamp_header(None);
+        let mut request = request(Method::GET, "https://api.example.com/");
+        request
+            .headers_mut()
+            .insert(header, HeaderValue::from_static("what do ya want for nothing?"));
+
+        signer.sign_at(&mut request, test_suite_time()).unwrap();
+        assert_eq!(
+            request.headers()["x-signature"],
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(request.headers().get("x-timestamp").is_none());
+    }
+
+    #[test]
+    fn hmac_signer_defaults_to_method_path_timestamp_and_body_hash() {
+        let mut request = request(Method::POST, "https://payments.example.com/v1/payments");
+        *request.body_mut() = Some(r#"{"amount":100}"#.into());
+
+        HmacSigner::new("my-secret").sign_at(&mut request, test_suite_time()).unwrap();
+        assert_eq!(request.headers()["x-timestamp"], "1440938160");
+        assert_eq!(
+            request.headers()["x-signature"],
+            "d319e30152a6cc00a9ea9b9457fb54b120e368d1c4b69330dd6b4273a611094d"
+        );
+    }
+
+    #[test]
+    fn hmac_signer_applies_its_configuration() {
+        let signer = HmacSigner::new("my-secret")
+            .key_id(HeaderName::from_static("x-key-id"), "key-1")
+            .components([
+                SignedComponent::Timestamp,
+                SignedComponent::Method,
+                SignedComponent::PathAndQuery,
+                SignedComponent::BodyHash,
+            ])
+            .separator("|")
+            .signature_prefix("sha256=")
+            .encoding(SignatureEncoding::Base64);
+        let mut request = request(Method::POST, "https://payments.example.com/v1/payments?limit=10");
+
+        signer.sign_at(&mut request, test_suite_time()).unwrap();
+        assert_eq!(request.headers()["x-key-id"], "key-1");
+        assert_eq!(
+            request.headers()["x-signature"],
+            "sha256=Z4E6PNnn05YuHOn5pUySdrmes9LkYEt2pyS5aNYx5kU="
+        );
+    }
+
+    #[test]
+    fn timestamps_are_formatted_in_utc() {
+        let time = UtcTime::new(test_suite_time());
+        assert_eq!(time.date(), "20150830");
+        assert_eq!(time.basic(), "20150830T123600Z");
+        assert_eq!(time.rfc3339(), "2015-08-30T12:36:00Z");
+        assert_eq!(UtcTime::new(UNIX_EPOCH + Duration::from_secs(951_782_400)).rfc3339(), "2000-02-29T00:00:00Z");
+    }
+}
@@ -4,7 +4,14 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::fmt::Debug;
 use tracing::{debug, error, info};
+use crate::error::ErrorType;
 use crate::ApiResult;
+use crate::auth::{authenticate, renew_credentials, ApiKeyLocation, AuthProvider};
+use crate::client::shared_http_client;
+use crate::decoder::{default_decoders, DecoderRegistry};
+use crate::response::{decode_response, ApiResponse};
+use crate::runtime::Instant;
+use crate::retry::{send_with_retry, RetryPolicy};
 
 /// Enum representing different methods for transmitting data in an HTTP request.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,15 +24,39 @@ pub enum DataTransmissionMethod {
     FormData,
     /// Data sent as multipart form data (for file uploads).
     Multipart,
+    /// A `StreamBody` field sent as the whole body, for uploads too large to hold in memory.
+    Stream,
+    /// A `RawBody` field, such as bytes or text, sent verbatim as the whole body.
+    Raw,
+    /// Data sent as an XML body.
+    Xml,
+    /// Data sent as a MessagePack body. Requires the `msgpack` feature.
+    MessagePack,
+    /// Data sent as a CBOR body. Requires the `cbor` feature.
+    Cbor,
+    /// A `prost::Message` field sent as a Protobuf body. Requires the `protobuf` feature.
+    Protobuf,
 }
 
 /// Enum representing different methods for authentication in an HTTP request.
+///
+/// The credentials come from the `AuthProvider` of the client or call. Requests with `None` are
+/// sent without credentials.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AuthenticationMethod {
     /// Bearer token authentication.
     Bearer,
     /// Basic authentication (username and password).
     Basic,
+    /// Digest authentication (username and password), see `DigestAuth`.
+    Digest,
+    /// An API key sent as the named header, query parameter or cookie, see `ApiKeyAuth`.
+    ApiKey {
+        /// Where the key is sent.
+        location: ApiKeyLocation,
+        /// The name of the header, query parameter or cookie, e.g. `X-Api-Key`.
+        name: &'static str,
+    },
     /// No authentication.
     None,
 }
@@ -38,6 +69,7 @@ pub enum AuthenticationMethod {
 /// # Type Parameters
 ///
 /// - `Res`: The response type that will be deserialized from the API response. Defaults to `()`.
+///   Empty bodies are only accepted by `()`, `Option<T>` and `EmptyResponse`.
 ///
 /// # Requirements
 ///
@@ -52,12 +84,19 @@ pub enum AuthenticationMethod {
 /// - `METHOD`: The HTTP method (default is `GET`).
 /// - `DATA_TRANSMISSION_METHOD`: Specifies how the request data is sent (default is `QueryParams`).
 /// - `AUTHENTICATION_METHOD`: Specifies the authentication method (default is `None`).
+/// - `RETRY_POLICY`: Overrides the retry policy for this request (default is `None`).
+/// - `ERROR_TYPE`: The type error bodies are deserialized into (default is `None`).
 ///
 /// # Methods
 ///
 /// - `generate_request`: Generates a `reqwest::RequestBuilder` based on the request type.
 /// - `send_request`: Sends the request asynchronously and returns the response.
 /// - `send_and_parse`: Sends the request and parses the response, returning a result or an error.
+/// - `send_and_parse_with_meta`: Like `send_and_parse`, also returning the status, headers, final
+///   URL and elapsed time as an `ApiResponse`.
+///
+/// The `send_*` methods use a process-wide `reqwest::Client`. Use [`ApiClient`](crate::ApiClient)
+/// to configure the base URL, default headers and credentials once and reuse them.
 ///
 /// # Example
 ///
@@ -77,7 +116,7 @@ pub enum AuthenticationMethod {
 ///     custom_header: Option<String>,
 /// }
 ///
-/// #[derive(Deserialize, Debug, Default)]
+/// #[derive(Deserialize, Debug)]
 /// struct MyResponse {
 ///     result: String,
 /// }
@@ -98,11 +137,11 @@ pub enum AuthenticationMethod {
 ///     Ok(())
 /// }
 /// ```
-#[cfg_attr(feature = "native", allow(async_fn_in_trait))]
+#[cfg_attr(any(feature = "native", feature = "wasm"), allow(async_fn_in_trait))]
 pub trait ApiRequest<Res = ()>
 where
     Self: Serialize + Debug,
-    Res: Default + DeserializeOwned,
+    Res: DeserializeOwned,
 {
     /// A static string representing the endpoint for the request.
     const ENDPOINT: &'static str;
@@ -118,120 +157,128 @@ where
     /// The default is `AuthenticationMethod::None`.
     const AUTHENTICATION_METHOD: AuthenticationMethod = AuthenticationMethod::None;
 
-    /// Parses a response into the expected type.
-    /// This method handles different content types and formats.
-    async fn from_response(resp: reqwest::Response) -> ApiResult<Res> {
-        debug!("Received response: {:?}", resp);
-        let status = resp.status();
+    /// Specifies how failed requests are retried.
+    /// The default is `None`, which falls back to the client's policy or sends the request once.
+    const RETRY_POLICY: Option<RetryPolicy> = None;
 
-        // Check if the response is successful
-        if !status.is_success() {
-            return Err(ApiForgeError::ResponseError {
-                status,
-                message: resp.text().await.unwrap_or_default(),
-            });
-        }
-        
-        if status == reqwest::StatusCode::NO_CONTENT {
-            debug!("Status is 204 No Content, returning default value.");
-            return Ok(Res::default());
-        }
+    /// Specifies the type error response bodies are deserialized into, returned as
+    /// `ApiForgeError::Api`.
+    /// The default is `None`, which falls back to the client's error type or keeps the raw text.
+    const ERROR_TYPE: Option<ErrorType> = None;
 
-        // Check for empty body or 204 No Content status
-        if resp.content_length().unwrap_or(0) == 0 {
-            debug!("Response is empty, returning default value.");
-            return Ok(Res::default());
-        }
+    /// Parses a response into the expected type using the default `DecoderRegistry`.
+    async fn from_response(resp: reqwest::Response) -> ApiResult<Res> {
+        Self::from_response_with(resp, default_decoders()).await
+    }
 
-        // Get content type to determine parsing strategy
-        let content_type = resp
-            .headers()
-            .get(reqwest::header::CONTENT_TYPE)
-            .map(|v| v.to_str().unwrap_or("").to_lowercase())
-            .unwrap_or_default();
+    /// Parses a response into the expected type, decoding the body with the decoder registered
+    /// for its `Content-Type`.
+    ///
+    /// `ApiClient` calls this method with its own registry, so override it rather than
+    /// `from_response` to customize parsing.
+    async fn from_response_with(resp: reqwest::Response, decoders: &DecoderRegistry) -> ApiResult<Res> {
+        debug!("Received response: {:?}", resp);
+        let status = resp.status();
+        let headers = resp.headers().clone();
 
-        debug!("Content-Type: {}", content_type);
+        let body = match resp.bytes().await {
+            Ok(body) => body,
+            // The status is reported for failed responses even if the body can't be read
+            Err(_) if !status.is_success() => Default::default(),
+            Err(e) => {
+                error!("Failed to read response body: {}", e);
+                return Err(ApiForgeError::ParseError(e));
+            }
+        };
 
-        // Parse based on content type
-        if content_type.contains("application/json") {
-            debug!("Parsing response as JSON");
-            resp.json::<Res>().await.map_err(|e| {
-                error!("JSON parsing error: {}", e);
-                ApiForgeError::ParseError(e)
-            })
-        } else if content_type.contains("application/xml") || content_type.contains("text/xml") {
-            debug!("Parsing response as XML");
-            let text = resp.text().await.map_err(|e| {
-                error!("Failed to get text from response: {}", e);
-                ApiForgeError::ParseError(e)
-            })?;
+        decode_response(status, &headers, &body, decoders, Self::ERROR_TYPE)
+    }
 
-            serde_xml_rust::from_str::<Res>(&text).map_err(|e| {
-                error!("XML parsing error: {}", e);
-                ApiForgeError::XmlParseError(e)
-            })
-        } else if content_type.contains("text/plain") {
-            // For text/plain, we just try JSON as a fallback
-            debug!("Content-Type is text/plain, attempting JSON parsing as fallback");
-            resp.json::<Res>().await.map_err(|e| {
-                error!("Failed to parse text/plain as JSON: {}", e);
-                ApiForgeError::ParseError(e)
-            })
-        } else {
-            // Try json as a last resort
-            debug!("Unknown content type: {}, trying JSON as fallback", content_type);
-            resp.json::<Res>().await.map_err(|e| {
-                error!("Failed to parse unknown content type: {}", e);
-                ApiForgeError::UnsupportedContentType(content_type)
-            })
-        }
+    /// Whether the request can be built and sent more than once.
+    ///
+    /// Requests with a body that can only be sent once, such as a `StreamBody`, return `false`.
+    /// They are never retried or resent after an `AuthProvider` renews its credentials, so the
+    /// first response is returned. The derive implements this for `Stream` requests and
+    /// `Multipart` requests with `StreamBody` parts.
+    fn is_replayable(&self) -> bool {
+        true
     }
 
     /// Optional: Provides multipart form data for file uploads.
     /// Override this method when using `DataTransmissionMethod::Multipart`.
-    fn multipart_form_data(&self) -> reqwest::multipart::Form {
+    ///
+    /// Fails if a part can't be serialized, so the request is never sent with missing fields.
+    fn multipart_form_data(&self) -> ApiResult<reqwest::multipart::Form> {
         debug!("Using default empty multipart form. Override multipart_form_data() if needed.");
-        reqwest::multipart::Form::new()
+        Ok(reqwest::multipart::Form::new())
     }
 
     /// Generates a `reqwest::RequestBuilder` based on the request's parameters.
     ///
     /// # Arguments
     ///
+    /// * `client` - The `reqwest::Client` used to build the request.
     /// * `base_url` - The base URL of the API.
     /// * `headers` - Optional headers to include in the request.
-    /// * `token` - Optional authentication token (and password for Basic auth).
+    ///
+    /// Credentials are applied to the built request by an `AuthProvider` when it is sent.
     ///
     /// # Returns
     ///
-    /// A configured `reqwest::RequestBuilder` ready to be sent.
+    /// A configured `reqwest::RequestBuilder` ready to be sent, or the error of encoding the
+    /// body, so a request is never sent with a body other than the one it describes.
     fn generate_request(
         &self,
+        client: &reqwest::Client,
         base_url: &str,
         headers: Option<HeaderMap>,
-        token: Option<(String, Option<String>)>,
-    ) -> reqwest::RequestBuilder;
+    ) -> ApiResult<reqwest::RequestBuilder>;
 
     /// Sends the request asynchronously and returns the raw response.
     ///
+    /// The request is retried according to `RETRY_POLICY`, if set and the request is replayable.
+    /// It isn't signed, as signers are only run by `ApiClient` and `BlockingApiClient`, see
+    /// `RequestSigner`.
+    ///
     /// # Arguments
     ///
     /// * `base_url` - The base URL of the API.
     /// * `headers` - Optional headers to include in the request.
-    /// * `token` - Optional authentication token (and password for Basic auth).
+    /// * `auth` - Optional provider applying the credentials, see `AuthProvider`.
     ///
     /// # Returns
     ///
-    /// A `Result` containing the raw response or a reqwest error.
+    /// A `Result` containing the raw response or an error.
     async fn send_request(
         &self,
         base_url: &str,
         headers: Option<HeaderMap>,
-        token: Option<(String, Option<String>)>,
-    ) -> reqwest::Result<reqwest::Response> {
+        auth: Option<&dyn AuthProvider>,
+    ) -> ApiResult<reqwest::Response> {
         info!("Sending request to {}{}...", base_url, Self::ENDPOINT);
         debug!("Request: {:?}", self);
-        self.generate_request(base_url, headers, token).send().await
+        // Bodies that can only be sent once are never retried or resent with new credentials
+        let replayable = self.is_replayable();
+        let policy = Self::RETRY_POLICY.filter(|_| replayable);
+        send_with_retry(policy.as_ref(), &Self::METHOD, || async {
+            let send = || async {
+                let mut request = self
+                    .generate_request(shared_http_client(), base_url, headers.clone())?
+                    .build()?;
+                authenticate(&mut request, auth, Self::AUTHENTICATION_METHOD, None).await?;
+                let sent = request.headers().clone();
+                ApiResult::<_>::Ok((sent, shared_http_client().execute(request).await?))
+            };
+
+            let (sent, response) = send().await?;
+            let renewed =
+                renew_credentials(auth, Self::AUTHENTICATION_METHOD, &sent, response.status(), response.headers()).await?;
+            if renewed && replayable {
+                return Ok(send().await?.1);
+            }
+            Ok(response)
+        })
+        .await
     }
 
     /// Sends the request and parses the response into the expected type.
@@ -240,7 +287,7 @@ where
     ///
     /// * `base_url` - The base URL of the API.
     /// * `headers` - Optional headers to include in the request.
-    /// * `token` - Optional authentication token (and password for Basic auth).
+    /// * `auth` - Optional provider applying the credentials, see `AuthProvider`.
     ///
     /// # Returns
     ///
@@ -249,10 +296,111 @@ where
         &self,
         base_url: &str,
         headers: Option<HeaderMap>,
-        token: Option<(String, Option<String>)>,
+        auth: Option<&dyn AuthProvider>,
     ) -> ApiResult<Res> {
-        let response = self.send_request(base_url, headers, token).await?;
+        let response = self.send_request(base_url, headers, auth).await?;
         debug!("Response status: {}", response.status());
         Self::from_response(response).await
     }
-}
\ No newline at end of file
+
+    /// Sends the request and parses the response, returning the body together with the status,
+    /// headers, final URL and elapsed time.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base URL of the API.
+    /// * `headers` - Optional headers to include in the request.
+    /// * `auth` - Optional provider applying the credentials, see `AuthProvider`.
+    async fn send_and_parse_with_meta(
+        &self,
+        base_url: &str,
+        headers: Option<HeaderMap>,
+        auth: Option<&dyn AuthProvider>,
+    ) -> ApiResult<ApiResponse<Res>> {
+        let start = Instant::now();
+        let response = self.send_request(base_url, headers, auth).await?;
+        debug!("Response status: {}", response.status());
+        let status = response.status();
+        let response_headers = response.headers().clone();
+        let url = response.url().clone();
+        let body = Self::from_response(response).await?;
+        Ok(ApiResponse {
+            body,
+            status,
+            headers: response_headers,
+            url,
+            elapsed: start.elapsed(),
+        })
+    }
+
+    /// Sends the request with a blocking client and returns the raw response.
+    ///
+    /// Uses a process-wide `reqwest::blocking::Client` and must not be called from within an
+    /// async runtime. The request is retried according to `RETRY_POLICY`, if set. Like
+    /// `send_request`, it doesn't sign the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base URL of the API.
+    /// * `headers` - Optional headers to include in the request.
+    /// * `auth` - Optional provider applying the credentials, see `AuthProvider`.
+    #[cfg(feature = "blocking")]
+    fn send_request_blocking(
+        &self,
+        base_url: &str,
+        headers: Option<HeaderMap>,
+        auth: Option<&dyn AuthProvider>,
+    ) -> ApiResult<reqwest::blocking::Response> {
+        crate::blocking::send_blocking(
+            self,
+            crate::blocking::shared_blocking_client(),
+            base_url,
+            headers,
+            auth,
+            None,
+            None,
+        )
+    }
+
+    /// Sends the request with a blocking client and parses the response into the expected type.
+    ///
+    /// The response is parsed with the same logic as `from_response`.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base URL of the API.
+    /// * `headers` - Optional headers to include in the request.
+    /// * `auth` - Optional provider applying the credentials, see `AuthProvider`.
+    #[cfg(feature = "blocking")]
+    fn send_and_parse_blocking(
+        &self,
+        base_url: &str,
+        headers: Option<HeaderMap>,
+        auth: Option<&dyn AuthProvider>,
+    ) -> ApiResult<Res> {
+        let response = self.send_request_blocking(base_url, headers, auth)?;
+        debug!("Response status: {}", response.status());
+        crate::blocking::from_blocking_response(response, default_decoders(), Self::ERROR_TYPE)
+    }
+
+    /// Sends the request with a blocking client and parses the response, returning the body
+    /// together with the status, headers, final URL and elapsed time.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base URL of the API.
+    /// * `headers` - Optional headers to include in the request.
+    /// * `auth` - Optional provider applying the credentials, see `AuthProvider`.
+    #[cfg(feature = "blocking")]
+    fn send_and_parse_blocking_with_meta(
+        &self,
+        base_url: &str,
+        headers: Option<HeaderMap>,
+        auth: Option<&dyn AuthProvider>,
+    ) -> ApiResult<ApiResponse<Res>> {
+        let start = Instant::now();
+        let response = self.send_request_blocking(base_url, headers, auth)?;
+        debug!("Response status: {}", response.status());
+        crate::blocking::from_blocking_response_with_meta(response, start, default_decoders(), Self::ERROR_TYPE)
+    }
+}
@@ -0,0 +1,338 @@
+use crate::error::BoxError;
+use crate::{ApiForgeError, ApiResult};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use http_body::{Frame, SizeHint};
+use http_body_util::BodyDataStream;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, ResponseBuilderExt, StatusCode, Url};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use sync_wrapper::SyncWrapper;
+
+/// A stream of body chunks.
+pub type BodyStream = Pin<Box<dyn Stream<Item = Result<Bytes, BoxError>> + Send>>;
+
+/// The body of a `TransportRequest` or `TransportResponse`.
+pub enum TransportBody {
+    /// No body.
+    Empty,
+    /// A body held in memory.
+    Bytes(Bytes),
+    /// A body produced chunk by chunk, e.g. a file upload or a download.
+    Stream(BodyStream),
+}
+
+impl TransportBody {
+    /// Wraps a stream of chunks as a body.
+    pub fn stream<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, BoxError>> + Send + 'static,
+    {
+        Self::Stream(Box::pin(stream))
+    }
+
+    /// Returns the body if it is held in memory.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Empty => Some(&[]),
+            Self::Bytes(bytes) => Some(bytes),
+            Self::Stream(_) => None,
+        }
+    }
+
+    /// Reads the whole body into memory.
+    pub async fn collect(self) -> ApiResult<Bytes> {
+        match self {
+            Self::Empty => Ok(Bytes::new()),
+            Self::Bytes(bytes) => Ok(bytes),
+            Self::Stream(stream) => {
+                let chunks = stream
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .map_err(ApiForgeError::TransportError)?;
+                Ok(chunks.concat().into())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for TransportBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("Empty"),
+            Self::Bytes(bytes) => f.debug_tuple("Bytes").field(&bytes.len()).finish(),
+            Self::Stream(_) => f.write_str("Stream"),
+        }
+    }
+}
+
+impl From<Bytes> for TransportBody {
+    fn from(bytes: Bytes) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl From<Vec<u8>> for TransportBody {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes.into())
+    }
+}
+
+impl From<String> for TransportBody {
+    fn from(text: String) -> Self {
+        Self::Bytes(text.into())
+    }
+}
+
+impl From<&'static str> for TransportBody {
+    fn from(text: &'static str) -> Self {
+        Self::Bytes(Bytes::from_static(text.as_bytes()))
+    }
+}
+
+impl From<reqwest::Body> for TransportBody {
+    fn from(body: reqwest::Body) -> Self {
+        match body.as_bytes() {
+            Some(bytes) => Self::Bytes(Bytes::copy_from_slice(bytes)),
+            None => Self::stream(BodyDataStream::new(body).map_err(BoxError::from)),
+        }
+    }
+}
+
+impl From<TransportBody> for reqwest::Body {
+    fn from(body: TransportBody) -> Self {
+        body.into_reqwest_body(None)
+    }
+}
+
+impl TransportBody {
+    /// Converts the body for reqwest, reporting `len` as the exact size of a streaming body.
+    pub(crate) fn into_reqwest_body(self, len: Option<u64>) -> reqwest::Body {
+        match (self, len) {
+            (Self::Empty, _) => reqwest::Body::from(Bytes::new()),
+            (Self::Bytes(bytes), _) => reqwest::Body::from(bytes),
+            (Self::Stream(stream), Some(len)) => reqwest::Body::wrap(SizedStream {
+                stream: SyncWrapper::new(stream),
+                len,
+            }),
+            (Self::Stream(stream), None) => reqwest::Body::wrap_stream(stream),
+        }
+    }
+}
+
+/// A streaming body with a known length.
+///
+/// Reporting the exact size lets reqwest send a `Content-Length` instead of chunked encoding,
+/// and keeps `reqwest::Response::content_length` working for streamed responses.
+struct SizedStream {
+    stream: SyncWrapper<BodyStream>,
+    len: u64,
+}
+
+impl http_body::Body for SizedStream {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, BoxError>>> {
+        self.get_mut()
+            .stream
+            .get_mut()
+            .as_mut()
+            .poll_next(cx)
+            .map(|chunk| chunk.map(|chunk| chunk.map(Frame::data)))
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.len)
+    }
+}
+
+/// Returns the `Content-Length` header value, if present and valid.
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(reqwest::header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// A transport-agnostic description of an outgoing HTTP request.
+#[derive(Debug)]
+pub struct TransportRequest {
+    /// The HTTP method.
+    pub method: Method,
+    /// The full URL, including query parameters.
+    pub url: Url,
+    /// The request headers.
+    pub headers: HeaderMap,
+    /// The request body.
+    pub body: TransportBody,
+    /// The timeout for the whole request, if any.
+    pub timeout: Option<Duration>,
+}
+
+impl From<reqwest::Request> for TransportRequest {
+    fn from(mut request: reqwest::Request) -> Self {
+        let body = request.body_mut().take();
+
+        // Keep the length of streaming bodies, which is otherwise lost in the conversion
+        let len = body
+            .as_ref()
+            .filter(|body| body.as_bytes().is_none())
+            .and_then(|body| http_body::Body::size_hint(body).exact());
+        if let Some(len) = len {
+            if !request.headers().contains_key(reqwest::header::CONTENT_LENGTH) {
+                request.headers_mut().insert(reqwest::header::CONTENT_LENGTH, len.into());
+            }
+        }
+
+        let body = body.map(TransportBody::from).unwrap_or(TransportBody::Empty);
+
+        Self {
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers: std::mem::take(request.headers_mut()),
+            body,
+            timeout: request.timeout().copied(),
+        }
+    }
+}
+
+impl From<TransportRequest> for reqwest::Request {
+    fn from(request: TransportRequest) -> Self {
+        let mut converted = reqwest::Request::new(request.method, request.url);
+        let len = content_length(&request.headers);
+        *converted.headers_mut() = request.headers;
+        *converted.timeout_mut() = request.timeout;
+        if !matches!(request.body, TransportBody::Empty) {
+            *converted.body_mut() = Some(request.body.into_reqwest_body(len));
+        }
+        converted
+    }
+}
+
+/// A transport-agnostic description of an incoming HTTP response.
+#[derive(Debug)]
+pub struct TransportResponse {
+    /// The response status.
+    pub status: StatusCode,
+    /// The response headers.
+    pub headers: HeaderMap,
+    /// The final URL of the response, after redirects.
+    pub url: Url,
+    /// The response body.
+    pub body: TransportBody,
+}
+
+impl TransportResponse {
+    /// Creates a response with the given status and body, no headers and a placeholder URL.
+    pub fn new(status: StatusCode, body: impl Into<TransportBody>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            url: Url::parse("http://no.url.provided.local").expect("placeholder URL is valid"),
+            body: body.into(),
+        }
+    }
+
+    /// Adds a header to the response.
+    pub fn with_header(mut self, name: reqwest::header::HeaderName, value: reqwest::header::HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sets the final URL of the response.
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = url;
+        self
+    }
+}
+
+impl From<TransportResponse> for reqwest::Response {
+    fn from(response: TransportResponse) -> Self {
+        let len = content_length(&response.headers);
+        let mut converted = http::Response::builder()
+            .status(response.status)
+            .url(response.url)
+            .body(response.body.into_reqwest_body(len))
+            .expect("status and URL are already validated");
+        *converted.headers_mut() = response.headers;
+        converted.into()
+    }
+}
+
+/// Sends HTTP requests on behalf of an `ApiClient`.
+///
+/// Implement this trait to use an HTTP stack other than reqwest's default one, such as hyper,
+/// a platform-specific client or an in-memory mock for tests. Any closure taking a
+/// `TransportRequest` and returning a future of `ApiResult<TransportResponse>` is a transport.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{ApiClient, ApiResult, TransportRequest, TransportResponse};
+/// use reqwest::StatusCode;
+///
+/// let client = ApiClient::builder("http://mock.local")
+///     .transport(|request: TransportRequest| async move {
+///         let body = format!(r#"{{"path":"{}"}}"#, request.url.path());
+///         ApiResult::Ok(TransportResponse::new(StatusCode::OK, body))
+///     })
+///     .build();
+/// ```
+#[async_trait]
+pub trait Transport: Send + Sync + 'static {
+    /// Sends the request and returns the response.
+    async fn send(&self, request: TransportRequest) -> ApiResult<TransportResponse>;
+}
+
+#[async_trait]
+impl<F, Fut> Transport for F
+where
+    F: Fn(TransportRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ApiResult<TransportResponse>> + Send,
+{
+    async fn send(&self, request: TransportRequest) -> ApiResult<TransportResponse> {
+        self(request).await
+    }
+}
+
+/// The default transport, sending requests with a `reqwest::Client`.
+///
+/// Response bodies are streamed rather than buffered.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    /// Creates a transport sending requests with the given client.
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// Returns the underlying `reqwest::Client`.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl Transport for ReqwestTransport {
+    async fn send(&self, request: TransportRequest) -> ApiResult<TransportResponse> {
+        let response = self.client.execute(request.into()).await?;
+
+        Ok(TransportResponse {
+            status: response.status(),
+            headers: response.headers().clone(),
+            url: response.url().clone(),
+            body: TransportBody::stream(response.bytes_stream().map(|chunk| chunk.map_err(BoxError::from))),
+        })
+    }
+}
@@ -0,0 +1,70 @@
+//! Helpers for exposing requests to JavaScript through `wasm-bindgen`.
+//!
+//! Rust futures can't be returned from `#[wasm_bindgen]` functions directly, so [`into_promise`]
+//! turns the result of `send_and_parse` or `ApiClient::execute` into a `js_sys::Promise` that
+//! resolves to the serialized response and rejects with a JS `Error`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use api_forge::{wasm::into_promise, ApiRequest, Request};
+//! use serde::{Deserialize, Serialize};
+//! use wasm_bindgen::prelude::*;
+//!
+//! #[derive(Serialize, Debug, Request)]
+//! #[request(endpoint = "/posts", response_type = "Vec<Post>")]
+//! struct GetPosts;
+//!
+//! #[derive(Serialize, Deserialize, Debug)]
+//! struct Post {
+//!     id: i32,
+//!     title: String,
+//! }
+//!
+//! #[wasm_bindgen]
+//! pub fn get_posts() -> js_sys::Promise {
+//!     into_promise(async move {
+//!         GetPosts.send_and_parse("https://jsonplaceholder.typicode.com", None, None).await
+//!     })
+//! }
+//! ```
+
+use crate::{ApiForgeError, ApiResult};
+use serde::Serialize;
+use std::future::Future;
+use wasm_bindgen::JsValue;
+
+/// Converts a request future into a `js_sys::Promise`.
+///
+/// The successful value is serialized with `serde-wasm-bindgen`, so maps become plain JS objects.
+/// Errors reject the promise with a JS `Error`, see `From<ApiForgeError> for JsValue`.
+pub fn into_promise<F, T>(future: F) -> js_sys::Promise
+where
+    F: Future<Output = ApiResult<T>> + 'static,
+    T: Serialize,
+{
+    wasm_bindgen_futures::future_to_promise(async move {
+        let value = future.await?;
+        to_js_value(&value)
+    })
+}
+
+/// Serializes a value into a `JsValue`, with maps converted to plain JS objects.
+pub fn to_js_value<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsValue> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(Into::into)
+}
+
+/// Converts the error into a JS `Error` with the error message.
+///
+/// For response errors, the HTTP status is available as the `status` property of the error.
+impl From<ApiForgeError> for JsValue {
+    fn from(err: ApiForgeError) -> Self {
+        let js_error = js_sys::Error::new(&err.to_string());
+        if let Some(status) = err.status_code() {
+            let _ = js_sys::Reflect::set(&js_error, &"status".into(), &status.as_u16().into());
+        }
+        js_error.into()
+    }
+}
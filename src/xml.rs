@@ -0,0 +1,172 @@
+use crate::decoder::{utf8_body, DecodeSeed, MediaType, ResponseDecoder};
+use crate::error::ApiForgeError;
+use crate::ApiResult;
+use serde::Serialize;
+use std::borrow::Cow;
+use tracing::{debug, error};
+
+/// Options for XML request bodies sent with `DataTransmissionMethod::Xml`.
+///
+/// # Example
+///
+/// ```rust
+/// use api_forge::{to_xml_string, XmlOptions};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Order {
+///     id: u32,
+/// }
+///
+/// const OPTIONS: XmlOptions = XmlOptions::new()
+///     .with_root("PurchaseOrder")
+///     .with_namespaces(&[("", "urn:example:orders"), ("xsi", "http://www.w3.org/2001/XMLSchema-instance")]);
+///
+/// let xml = to_xml_string(&Order { id: 1 }, &OPTIONS).unwrap();
+/// assert!(xml.ends_with(r#"<PurchaseOrder xmlns="urn:example:orders" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><id>1</id></PurchaseOrder>"#));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct XmlOptions {
+    /// Name of the root element. Defaults to the name of the serialized struct.
+    pub root: Option<&'static str>,
+    /// Namespace declarations added to the root element as `(prefix, uri)`. An empty prefix
+    /// declares the default namespace.
+    pub namespaces: &'static [(&'static str, &'static str)],
+}
+
+impl XmlOptions {
+    /// Creates options keeping the struct name as root element, without namespaces.
+    pub const fn new() -> Self {
+        Self {
+            root: None,
+            namespaces: &[],
+        }
+    }
+
+    /// Sets the name of the root element.
+    pub const fn with_root(mut self, root: &'static str) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    /// Sets the namespace declarations added to the root element.
+    pub const fn with_namespaces(mut self, namespaces: &'static [(&'static str, &'static str)]) -> Self {
+        self.namespaces = namespaces;
+        self
+    }
+}
+
+/// Serializes the value as an XML document using `serde_xml_rust`, applying the root element name
+/// and namespace declarations of the options.
+pub fn to_xml_string<T: Serialize>(value: &T, options: &XmlOptions) -> ApiResult<String> {
+    let xml = serde_xml_rust::to_string(value)?;
+    rewrite_root(xml, options)
+}
+
+/// Creates the request body for `DataTransmissionMethod::Xml`.
+pub fn xml_body<T: Serialize>(value: &T, options: &XmlOptions) -> ApiResult<reqwest::Body> {
+    to_xml_string(value, options).map(Into::into)
+}
+
+/// Renames the root element and adds the namespace declarations to it.
+fn rewrite_root(xml: String, options: &XmlOptions) -> ApiResult<String> {
+    if options.root.is_none() && options.namespaces.is_empty() {
+        return Ok(xml);
+    }
+
+    // The root element is the first tag that isn't the XML declaration
+    let root_start = xml
+        .match_indices('<')
+        .map(|(index, _)| index + 1)
+        .find(|&index| !xml[index..].starts_with('?'))
+        .ok_or_else(|| ApiForgeError::validation("XML request body has no root element"))?;
+    let root_end = xml[root_start..]
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .map(|index| root_start + index)
+        .unwrap_or(xml.len());
+    let name = &xml[root_start..root_end];
+    let root = options.root.unwrap_or(name);
+
+    let mut rewritten = String::with_capacity(xml.len() + 64);
+    rewritten.push_str(&xml[..root_start]);
+    rewritten.push_str(root);
+    for (prefix, uri) in options.namespaces {
+        let attribute = if prefix.is_empty() {
+            "xmlns".to_string()
+        } else {
+            format!("xmlns:{}", prefix)
+        };
+        rewritten.push_str(&format!(" {}=\"{}\"", attribute, escape_attribute(uri)));
+    }
+
+    let rest = &xml[root_end..];
+    let closing_tag = format!("</{}>", name);
+    match rest.strip_suffix(&closing_tag) {
+        Some(content) => {
+            rewritten.push_str(content);
+            rewritten.push_str(&format!("</{}>", root));
+        }
+        None => rewritten.push_str(rest),
+    }
+
+    Ok(rewritten)
+}
+
+/// Escapes a value for use in a double-quoted XML attribute.
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
+}
+
+/// Removes the `encoding` of the XML declaration of a document transcoded to UTF-8, so it isn't
+/// decoded again in the declared encoding.
+fn strip_encoding_declaration(mut xml: Vec<u8>) -> Vec<u8> {
+    if !xml.starts_with(b"<?xml") {
+        return xml;
+    }
+    let Some(end) = xml.windows(2).position(|window| window == b"?>") else {
+        return xml;
+    };
+    let Some(start) = xml[..end].windows(9).position(|window| window == b" encoding") else {
+        return xml;
+    };
+
+    // Skip the name, `=` and the quoted value
+    let mut index = start + b" encoding".len();
+    let mut quote = None;
+    while index < end {
+        match (xml[index], quote) {
+            (b'"' | b'\'', None) => quote = Some(xml[index]),
+            (c, Some(open)) if c == open => {
+                index += 1;
+                break;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    xml.drain(start..index);
+    xml
+}
+
+/// Decodes XML bodies with `serde_xml_rust`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XmlDecoder;
+
+impl ResponseDecoder for XmlDecoder {
+    fn decode(&self, body: &[u8], media_type: &MediaType, seed: DecodeSeed<'_>) -> ApiResult<()> {
+        debug!("Parsing response as XML");
+        let body = match utf8_body(body, media_type)? {
+            Cow::Borrowed(body) => Cow::Borrowed(body),
+            // The charset of the Content-Type takes precedence over the XML declaration (RFC 7303)
+            Cow::Owned(transcoded) => Cow::Owned(strip_encoding_declaration(transcoded)),
+        };
+        let mut deserializer = serde_xml_rust::Deserializer::new_from_reader(body.as_ref());
+        seed.deserialize(&mut deserializer).map_err(|e| {
+            error!("XML parsing error: {}", e);
+            ApiForgeError::XmlParseError(e)
+        })
+    }
+}
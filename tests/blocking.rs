@@ -0,0 +1,118 @@
+mod common;
+
+use api_forge::{ApiForgeError, BlockingApiClient, DecoderRegistry, OAuth2Auth, Request};
+use common::{Reply, TestServer};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Post {
+    id: i32,
+    title: String,
+}
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/posts/1", response_type = "Post", authentication = Bearer)]
+struct GetPost;
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/uploads", method = POST, transmission = Multipart, response_type = "Post")]
+struct Upload {
+    #[request(part)]
+    title: String,
+    #[serde(skip)]
+    #[request(file, file_name = "notes.txt", mime = "text/plain")]
+    notes: Vec<u8>,
+    #[serde(skip)]
+    #[request(file)]
+    attachment: PathBuf,
+}
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/ping", method = POST)]
+struct Ping;
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/orders")]
+struct ListOrders;
+
+#[derive(Deserialize, Debug)]
+struct OrderError {
+    code: u32,
+}
+
+#[test]
+fn oauth2_tokens_are_fetched_in_the_blocking_client() {
+    let server = TestServer::start(|request| match request.path.as_str() {
+        "/token" => Reply::json(r#"{"access_token":"token-1","token_type":"Bearer","expires_in":3600}"#),
+        _ if request.header("authorization") == Some("Bearer token-1") => {
+            Reply::json(r#"{"id":1,"title":"Hello"}"#)
+        }
+        _ => Reply::new(401, ""),
+    });
+
+    let auth = OAuth2Auth::client_credentials(format!("{}/token", server.url), "client", "secret");
+    let client = BlockingApiClient::builder(&server.url).auth(auth).build();
+
+    for _ in 0..2 {
+        let post = client.execute(&GetPost).unwrap();
+        assert_eq!(post, Post { id: 1, title: "Hello".to_string() });
+    }
+    assert_eq!(server.received_at("/token").len(), 1, "the token is cached");
+}
+
+#[test]
+fn multipart_requests_are_sent_by_the_blocking_client() {
+    let server = TestServer::start(|_| Reply::json(r#"{"id":2,"title":"Uploaded"}"#));
+    let attachment = std::env::temp_dir().join(format!("api-forge-blocking-{}.bin", std::process::id()));
+    std::fs::write(&attachment, b"file contents").unwrap();
+
+    let client = BlockingApiClient::new(&server.url);
+    let upload = Upload {
+        title: "Report".to_string(),
+        notes: b"some notes".to_vec(),
+        attachment: attachment.clone(),
+    };
+    let post = client.execute(&upload).unwrap();
+    std::fs::remove_file(&attachment).unwrap();
+    assert_eq!(post, Post { id: 2, title: "Uploaded".to_string() });
+
+    let received = server.received_at("/uploads");
+    assert_eq!(received.len(), 1);
+    let content_type = received[0].header("content-type").unwrap();
+    assert!(content_type.starts_with("multipart/form-data; boundary="), "{}", content_type);
+
+    let body = received[0].text();
+    assert!(body.contains("name=\"title\"\r\n\r\nReport\r\n"), "{}", body);
+    assert!(body.contains("filename=\"notes.txt\"\r\nContent-Type: text/plain\r\n\r\nsome notes\r\n"), "{}", body);
+    assert!(body.contains("\r\n\r\nfile contents\r\n"), "{}", body);
+}
+
+#[test]
+fn decoder_options_survive_replacing_the_decoders() {
+    let server = TestServer::start(|request| match request.path.as_str() {
+        "/ping" => Reply::new(200, ""),
+        _ => Reply::new(400, r#"{"code":7}"#).header("content-type", "application/json"),
+    });
+
+    let before = BlockingApiClient::builder(&server.url)
+        .strict_empty_body(true)
+        .error_type::<OrderError>()
+        .decoders(DecoderRegistry::default())
+        .build();
+    let after = BlockingApiClient::builder(&server.url)
+        .decoders(DecoderRegistry::default())
+        .strict_empty_body(true)
+        .error_type::<OrderError>()
+        .build();
+
+    for client in [before, after] {
+        let result = client.execute(&Ping);
+        assert!(matches!(result, Err(ApiForgeError::EmptyBody { .. })), "{:?}", result);
+
+        match client.execute(&ListOrders) {
+            Err(ApiForgeError::Api { body, .. }) => assert_eq!(body.downcast_ref::<OrderError>().unwrap().code, 7),
+            other => panic!("expected the error type, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use api_forge::{ApiClient, ApiForgeError, ApiRequest, ApiResult, Request, TransportRequest, TransportResponse};
+use reqwest::StatusCode;
+use serde::{Serialize, Serializer};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A value that always fails to serialize.
+#[derive(Debug)]
+struct Unserializable;
+
+impl Serialize for Unserializable {
+    fn serialize<S: Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("value can't be serialized"))
+    }
+}
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/orders", method = POST, transmission = Xml)]
+struct CreateXmlOrder {
+    id: u32,
+    item: Unserializable,
+}
+
+#[cfg(feature = "msgpack")]
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/orders", method = POST, transmission = MessagePack)]
+struct CreateMsgPackOrder {
+    id: u32,
+    item: Unserializable,
+}
+
+#[cfg(feature = "cbor")]
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/orders", method = POST, transmission = Cbor)]
+struct CreateCborOrder {
+    id: u32,
+    item: Unserializable,
+}
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/orders", method = POST, transmission = Multipart)]
+struct CreateMultipartOrder {
+    #[request(part)]
+    id: u32,
+    #[request(part)]
+    item: Option<Unserializable>,
+    #[request(json_part)]
+    details: Option<Unserializable>,
+}
+
+/// Returns a client counting the requests that reach its transport.
+fn client() -> (ApiClient, Arc<AtomicUsize>) {
+    let sent = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&sent);
+    let client = ApiClient::builder("http://mock.local")
+        .transport(move |_request: TransportRequest| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { ApiResult::Ok(TransportResponse::new(StatusCode::OK, "")) }
+        })
+        .build();
+    (client, sent)
+}
+
+#[tokio::test]
+async fn xml_encoding_errors_are_returned_before_sending() {
+    let (client, sent) = client();
+    let request = CreateXmlOrder {
+        id: 1,
+        item: Unserializable,
+    };
+
+    assert!(request
+        .generate_request(&reqwest::Client::new(), "http://mock.local", None)
+        .is_err());
+    let err = client.execute(&request).await.unwrap_err();
+    assert!(err.to_string().contains("can't be serialized"), "{}", err);
+    assert_eq!(sent.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn multipart_encoding_errors_are_returned_before_sending() {
+    let (client, sent) = client();
+    let valid = CreateMultipartOrder {
+        id: 1,
+        item: None,
+        details: None,
+    };
+    assert!(valid.multipart_form_data().is_ok());
+    client.execute(&valid).await.unwrap();
+    assert_eq!(sent.load(Ordering::SeqCst), 1);
+
+    for request in [
+        CreateMultipartOrder {
+            id: 1,
+            item: Some(Unserializable),
+            details: None,
+        },
+        CreateMultipartOrder {
+            id: 1,
+            item: None,
+            details: Some(Unserializable),
+        },
+    ] {
+        assert!(matches!(request.multipart_form_data(), Err(ApiForgeError::SerializeError(_))));
+        let err = client.execute(&request).await.unwrap_err();
+        assert!(matches!(err, ApiForgeError::SerializeError(_)), "{:?}", err);
+    }
+    assert_eq!(sent.load(Ordering::SeqCst), 1);
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn msgpack_encoding_errors_are_returned_before_sending() {
+    let (client, sent) = client();
+
+    let err = client
+        .execute(&CreateMsgPackOrder {
+            id: 1,
+            item: Unserializable,
+        })
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("can't be serialized"), "{}", err);
+    assert_eq!(sent.load(Ordering::SeqCst), 0);
+}
+
+#[cfg(feature = "cbor")]
+#[tokio::test]
+async fn cbor_encoding_errors_are_returned_before_sending() {
+    let (client, sent) = client();
+
+    let err = client
+        .execute(&CreateCborOrder {
+            id: 1,
+            item: Unserializable,
+        })
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("can't be serialized"), "{}", err);
+    assert_eq!(sent.load(Ordering::SeqCst), 0);
+}
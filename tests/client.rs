@@ -0,0 +1,248 @@
+use api_forge::{
+    async_trait, ApiClient, ApiClientBuilder, ApiForgeError, ApiRequest, ApiResult, AuthProvider,
+    AuthenticationMethod, BasicAuth, BearerAuth, DecoderRegistry, DigestAuth, Request, RetryPolicy, StreamBody,
+    TransportRequest, TransportResponse,
+};
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/artifacts", method = PUT, transmission = Stream, authentication = Bearer)]
+struct UploadArtifact {
+    #[serde(skip)]
+    #[request(body)]
+    artifact: StreamBody,
+}
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/uploads", method = PUT, transmission = Multipart)]
+struct UploadParts {
+    #[serde(skip)]
+    #[request(file)]
+    data: Vec<u8>,
+    #[serde(skip)]
+    #[request(file)]
+    stream: Option<StreamBody>,
+}
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/ping", method = POST)]
+struct Ping;
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/me", authentication = Bearer)]
+struct CurrentUser;
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/orders")]
+struct ListOrders;
+
+#[derive(Deserialize, Debug)]
+struct OrderError {
+    code: u32,
+}
+
+fn artifact() -> UploadArtifact {
+    let chunks = vec![Ok::<_, std::io::Error>(Bytes::from("chunk-1,")), Ok(Bytes::from("chunk-2"))];
+    UploadArtifact {
+        artifact: StreamBody::from_stream(futures_util::stream::iter(chunks)),
+    }
+}
+
+/// Returns a client answering every request with the status, recording the bodies it receives.
+fn client(status: StatusCode, auth: impl AuthProvider) -> (ApiClient, Arc<Mutex<Vec<Bytes>>>) {
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&bodies);
+    let client = ApiClient::builder("http://mock.local")
+        .retry_policy(RetryPolicy::new().with_jitter(false).with_base_delay(std::time::Duration::ZERO))
+        .auth(auth)
+        .transport(move |request: TransportRequest| {
+            let received = Arc::clone(&received);
+            async move {
+                let body = request.body.collect().await?;
+                received.lock().unwrap().push(body);
+                ApiResult::Ok(TransportResponse::new(status, ""))
+            }
+        })
+        .build();
+    (client, bodies)
+}
+
+/// Renews its credentials on every `401 Unauthorized`.
+#[derive(Default, Clone)]
+struct RenewingAuth {
+    renewals: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl AuthProvider for RenewingAuth {
+    async fn apply(&self, _request: &mut reqwest::Request, _method: AuthenticationMethod) -> ApiResult<()> {
+        Ok(())
+    }
+
+    async fn on_unauthorized(&self, _method: AuthenticationMethod, _headers: &HeaderMap) -> ApiResult<bool> {
+        self.renewals.fetch_add(1, Ordering::SeqCst);
+        Ok(true)
+    }
+}
+
+#[tokio::test]
+async fn stream_bodies_are_not_retried() {
+    let (client, bodies) = client(StatusCode::SERVICE_UNAVAILABLE, RenewingAuth::default());
+
+    let response = client.send(&artifact()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(*bodies.lock().unwrap(), vec![Bytes::from("chunk-1,chunk-2")]);
+
+    let err = client.execute(&artifact()).await.unwrap_err();
+    assert_eq!(err.status_code(), Some(StatusCode::SERVICE_UNAVAILABLE), "{}", err);
+}
+
+#[tokio::test]
+async fn stream_bodies_are_not_resent_after_renewing_credentials() {
+    let auth = RenewingAuth::default();
+    let (client, bodies) = client(StatusCode::UNAUTHORIZED, auth.clone());
+
+    let response = client.send(&artifact()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(bodies.lock().unwrap().len(), 1);
+    assert_eq!(auth.renewals.load(Ordering::SeqCst), 1, "the provider still sees the rejection");
+}
+
+#[tokio::test]
+async fn multipart_requests_with_stream_parts_are_not_retried() {
+    let (client, bodies) = client(StatusCode::SERVICE_UNAVAILABLE, RenewingAuth::default());
+
+    let replayable = UploadParts {
+        data: b"data".to_vec(),
+        stream: None,
+    };
+    assert!(replayable.is_replayable());
+    client.send(&replayable).await.unwrap();
+    assert_eq!(bodies.lock().unwrap().len(), 3, "replayable requests are retried");
+
+    bodies.lock().unwrap().clear();
+    let streamed = UploadParts {
+        data: b"data".to_vec(),
+        stream: Some(StreamBody::from_stream(futures_util::stream::iter(vec![Ok::<_, std::io::Error>(
+            Bytes::from("streamed"),
+        )]))),
+    };
+    assert!(!streamed.is_replayable());
+    client.send(&streamed).await.unwrap();
+    let bodies = bodies.lock().unwrap();
+    assert_eq!(bodies.len(), 1);
+    assert!(String::from_utf8_lossy(&bodies[0]).contains("streamed"));
+}
+
+#[tokio::test]
+async fn requests_without_a_response_type_decode_to_unit() {
+    let (client, _) = client(StatusCode::NO_CONTENT, RenewingAuth::default());
+
+    let response: () = client.execute(&Ping).await.unwrap();
+    assert_eq!(response, ());
+}
+
+/// Returns a client answering every request with `204 No Content`, recording the `Authorization`
+/// headers it receives.
+fn authorization_client(auth: impl AuthProvider) -> (ApiClient, Arc<Mutex<Vec<Option<String>>>>) {
+    let headers = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&headers);
+    let client = ApiClient::builder("http://mock.local")
+        .auth(auth)
+        .transport(move |request: TransportRequest| {
+            let authorization = request
+                .headers
+                .get(reqwest::header::AUTHORIZATION)
+                .map(|value| value.to_str().unwrap().to_string());
+            received.lock().unwrap().push(authorization);
+            async { ApiResult::Ok(TransportResponse::new(StatusCode::NO_CONTENT, "")) }
+        })
+        .build();
+    (client, headers)
+}
+
+#[tokio::test]
+async fn providers_reject_requests_using_another_method() {
+    let (client, headers) = authorization_client(BasicAuth::new("user", Some("pass".to_string())));
+
+    let result = client.execute(&CurrentUser).await;
+    assert!(matches!(result, Err(ApiForgeError::AuthError(_))), "{:?}", result);
+    assert!(headers.lock().unwrap().is_empty(), "the request must not be sent");
+
+    let (client, headers) = authorization_client(BearerAuth::new("token"));
+    client.execute(&CurrentUser).await.unwrap();
+    assert_eq!(*headers.lock().unwrap(), vec![Some("Bearer token".to_string())]);
+}
+
+#[tokio::test]
+async fn digest_credentials_are_only_sent_for_digest_requests() {
+    let auth = DigestAuth::new("user", "pass");
+    let (client, headers) = authorization_client(BearerAuth::new("token"));
+
+    let result = client.execute_with_auth(&CurrentUser, &auth).await;
+    assert!(matches!(result, Err(ApiForgeError::AuthError(_))), "{:?}", result);
+    assert!(headers.lock().unwrap().is_empty(), "the request must not be sent");
+
+    let mut challenge = HeaderMap::new();
+    challenge.insert(
+        reqwest::header::WWW_AUTHENTICATE,
+        "Digest realm=\"api\", qop=\"auth\", nonce=\"abc\"".parse().unwrap(),
+    );
+    let result = auth.on_unauthorized(AuthenticationMethod::Bearer, &challenge).await;
+    assert!(matches!(result, Err(ApiForgeError::AuthError(_))), "{:?}", result);
+    assert!(auth.on_unauthorized(AuthenticationMethod::Digest, &challenge).await.unwrap());
+}
+
+#[tokio::test]
+async fn providers_can_be_overridden_per_call() {
+    let (client, headers) = authorization_client(BearerAuth::new("client-token"));
+
+    client.execute_with_auth(&CurrentUser, &BearerAuth::new("user-token")).await.unwrap();
+    client.execute(&CurrentUser).await.unwrap();
+    assert_eq!(
+        *headers.lock().unwrap(),
+        vec![Some("Bearer user-token".to_string()), Some("Bearer client-token".to_string())]
+    );
+}
+
+/// Answers `/ping` with an empty `200 OK` and everything else with a JSON `400 Bad Request`.
+fn configured_client(builder: ApiClientBuilder) -> ApiClient {
+    builder
+        .transport(|request: TransportRequest| async move {
+            if request.url.path() == "/ping" {
+                return ApiResult::Ok(TransportResponse::new(StatusCode::OK, ""));
+            }
+            Ok(TransportResponse::new(StatusCode::BAD_REQUEST, r#"{"code":7}"#)
+                .with_header(reqwest::header::CONTENT_TYPE, "application/json".parse().unwrap()))
+        })
+        .build()
+}
+
+#[tokio::test]
+async fn decoder_options_survive_replacing_the_decoders() {
+    let before = ApiClient::builder("http://mock.local")
+        .strict_empty_body(true)
+        .error_type::<OrderError>()
+        .decoders(DecoderRegistry::default());
+    let after = ApiClient::builder("http://mock.local")
+        .decoders(DecoderRegistry::default())
+        .strict_empty_body(true)
+        .error_type::<OrderError>();
+
+    for client in [configured_client(before), configured_client(after)] {
+        assert!(client.decoders().is_strict());
+
+        let result = client.execute(&Ping).await;
+        assert!(matches!(result, Err(ApiForgeError::EmptyBody { .. })), "{:?}", result);
+
+        match client.execute(&ListOrders).await {
+            Err(ApiForgeError::Api { body, .. }) => assert_eq!(body.downcast_ref::<OrderError>().unwrap().code, 7),
+            other => panic!("expected the error type, got {:?}", other),
+        }
+    }
+}
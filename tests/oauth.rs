@@ -0,0 +1,174 @@
+use api_forge::{
+    ApiClient, ApiForgeError, AuthProvider, AuthenticationMethod, OAuth2Auth, Request, TransportRequest,
+    TransportResponse,
+};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Profile {
+    name: String,
+}
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/me", response_type = "Profile", authentication = Bearer)]
+struct GetProfile;
+
+#[derive(Serialize, Debug, Request)]
+#[request(endpoint = "/me", response_type = "Profile", authentication = Basic)]
+struct GetProfileWithPassword;
+
+/// Requests received by a mock transport.
+#[derive(Default, Clone)]
+struct Received {
+    token_requests: Arc<Mutex<Vec<TransportRequest>>>,
+    api_requests: Arc<AtomicUsize>,
+}
+
+/// Returns a client whose mock transport issues `token-N` for the N-th token request and
+/// accepts the token `accepted` returns for each API request.
+fn client(token_type: &'static str, accepted: fn(usize) -> &'static str) -> (ApiClient, Received) {
+    let received = Received::default();
+    let recorded = received.clone();
+    let client = ApiClient::builder("http://mock.local")
+        .transport(move |request: TransportRequest| {
+            let recorded = recorded.clone();
+            async move {
+                if request.url.path() == "/token" {
+                    let mut requests = recorded.token_requests.lock().unwrap();
+                    let body = format!(
+                        r#"{{"access_token":"token-{}","token_type":"{}","expires_in":3600}}"#,
+                        requests.len() + 1,
+                        token_type
+                    );
+                    requests.push(request);
+                    return Ok(json(StatusCode::OK, body));
+                }
+
+                let attempt = recorded.api_requests.fetch_add(1, Ordering::SeqCst);
+                let expected = format!("Bearer {}", accepted(attempt));
+                if request.headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok()) == Some(&expected) {
+                    Ok(json(StatusCode::OK, r#"{"name":"Ada"}"#.to_string()))
+                } else {
+                    Ok(TransportResponse::new(StatusCode::UNAUTHORIZED, ""))
+                }
+            }
+        })
+        .build();
+    (client, received)
+}
+
+fn json(status: StatusCode, body: String) -> TransportResponse {
+    TransportResponse::new(status, body).with_header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+}
+
+fn auth() -> OAuth2Auth {
+    OAuth2Auth::client_credentials("http://mock.local/token", "client", "secret")
+}
+
+#[tokio::test]
+async fn tokens_are_fetched_through_the_client_transport() {
+    let (client, received) = client("Bearer", |_| "token-1");
+    let auth = auth();
+
+    for _ in 0..2 {
+        let profile = client.execute_with_auth(&GetProfile, &auth).await.unwrap();
+        assert_eq!(profile, Profile { name: "Ada".to_string() });
+    }
+
+    let token_requests = received.token_requests.lock().unwrap();
+    assert_eq!(token_requests.len(), 1, "the token is cached");
+    let body = token_requests[0].body.as_bytes().unwrap();
+    assert_eq!(body, b"grant_type=client_credentials");
+}
+
+#[tokio::test]
+async fn rejected_tokens_are_replaced_once() {
+    let (client, received) = client("bearer", |attempt| if attempt == 0 { "token-0" } else { "token-2" });
+
+    let profile = client.execute_with_auth(&GetProfile, &auth()).await.unwrap();
+    assert_eq!(profile, Profile { name: "Ada".to_string() });
+    assert_eq!(received.token_requests.lock().unwrap().len(), 2);
+    assert_eq!(received.api_requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn tokens_renewed_in_the_meantime_are_kept() {
+    let (client, received) = client("Bearer", |_| "token-1");
+    let auth = auth();
+    client.execute_with_auth(&GetProfile, &auth).await.unwrap();
+
+    let mut sent = HeaderMap::new();
+    sent.insert(AUTHORIZATION, HeaderValue::from_static("Bearer token-0"));
+    let renewed = auth
+        .on_rejected(AuthenticationMethod::Bearer, &sent, &HeaderMap::new())
+        .await
+        .unwrap();
+    assert!(renewed, "the request is sent once more with the current token");
+
+    client.execute_with_auth(&GetProfile, &auth).await.unwrap();
+    assert_eq!(received.token_requests.lock().unwrap().len(), 1, "the current token is kept");
+
+    sent.insert(AUTHORIZATION, HeaderValue::from_static("Bearer token-1"));
+    auth.on_rejected(AuthenticationMethod::Bearer, &sent, &HeaderMap::new())
+        .await
+        .unwrap();
+    client.execute_with_auth(&GetProfile, &auth).await.unwrap_err();
+    assert_eq!(received.token_requests.lock().unwrap().len(), 3, "the rejected token is replaced");
+}
+
+#[tokio::test]
+async fn client_credentials_are_form_encoded_before_basic_encoding() {
+    let (client, received) = client("Bearer", |_| "token-1");
+    let auth = OAuth2Auth::client_credentials("http://mock.local/token", "client:1", "s3cr%t &more");
+
+    client.execute_with_auth(&GetProfile, &auth).await.unwrap();
+
+    let token_requests = received.token_requests.lock().unwrap();
+    let authorization = token_requests[0].headers.get(AUTHORIZATION).unwrap().to_str().unwrap();
+    let credentials = STANDARD.encode("client%3A1:s3cr%25t+%26more");
+    assert_eq!(authorization, format!("Basic {}", credentials));
+}
+
+#[tokio::test]
+async fn other_token_types_are_rejected() {
+    let (client, received) = client("mac", |_| "token-1");
+
+    let result = client.execute_with_auth(&GetProfile, &auth()).await;
+    assert!(matches!(result, Err(ApiForgeError::AuthError(_))), "{:?}", result);
+    assert_eq!(received.api_requests.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn requests_using_another_method_are_rejected() {
+    let (client, received) = client("Bearer", |_| "token-1");
+    let auth = auth();
+
+    let result = client.execute_with_auth(&GetProfileWithPassword, &auth).await;
+    assert!(matches!(result, Err(ApiForgeError::AuthError(_))), "{:?}", result);
+
+    let mut request = reqwest::Request::new(reqwest::Method::GET, "http://mock.local/me".parse().unwrap());
+    let result = auth.apply(&mut request, AuthenticationMethod::Basic).await;
+    assert!(matches!(result, Err(ApiForgeError::AuthError(_))), "{:?}", result);
+
+    assert!(received.token_requests.lock().unwrap().is_empty(), "no token is requested");
+    assert_eq!(received.api_requests.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn token_requests_time_out() {
+    let client = ApiClient::builder("http://mock.local")
+        .transport(|_: TransportRequest| futures_util::future::pending())
+        .build();
+    let auth = auth().timeout(Duration::from_millis(50));
+
+    let result = client.execute_with_auth(&GetProfile, &auth).await;
+    let error = result.unwrap_err();
+    assert!(error.to_string().contains("timed out"), "{}", error);
+}